@@ -1,15 +1,14 @@
 use clap::Parser;
 use methods::{FACTORS_ELF, FACTORS_ID};
-use risc0_zkvm::serde::to_vec;
+use program_args::Manifest;
+use serde_json::json;
 use subxt::{
 	ext::sp_core::{sr25519::Pair as SubxtPair, Pair as SubxtPairT},
 	tx::PairSigner,
 	OnlineClient, PolkadotConfig,
 };
 
-// Runtime types, etc
-#[subxt::subxt(runtime_metadata_path = "./metadata.scale")]
-pub mod substrate_node {}
+mod prover_mgmt;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -17,6 +16,9 @@ struct Args {
 	/// Whether to include an additional onchain request for a proof of the uploaded program
 	#[arg(short, long, default_value_t = true)]
 	with_request_proof: bool,
+	/// Number of blocks after which the proof request expires if it is not fulfilled
+	#[arg(long, default_value_t = 14_400)]
+	request_lifetime: u32,
 }
 
 #[tokio::main]
@@ -33,6 +35,8 @@ async fn main() {
 	let signer = PairSigner::new(restored_key);
 
 	let serialized_program = bincode::serialize(FACTORS_ELF).unwrap();
+	// Describes the inputs and outputs of the program, so that clients need not read its source
+	let manifest = Manifest::from_toml(include_str!("../../manifest.toml")).unwrap();
 
 	println!(
 		"Uploading program. Image id: {:?}",
@@ -41,10 +45,15 @@ async fn main() {
 
 	api.tx()
 		.sign_and_submit_then_watch_default(
-			&substrate_node::tx()
-				.prover_mgmt()
-				// Send the serialized elf file
-				.upload_program(FACTORS_ID, serialized_program),
+			// Send the serialized elf file, along with a name, description and manifest for it
+			&prover_mgmt::upload_program(
+				FACTORS_ID,
+				serialized_program,
+				b"factors".to_vec(),
+				manifest.description.as_bytes().to_vec(),
+				None,
+				Some(manifest.to_json()),
+			),
 			&signer,
 		)
 		.await
@@ -62,12 +71,22 @@ async fn main() {
 		let arg_2: u64 = 23_u64;
 
 		// Any args we want to pass to the program must be serialized using Risc0's serde serialized
-		// methods
-		let args = vec![to_vec(&17_u64).unwrap(), to_vec(&23_u64).unwrap()];
+		// methods, which the schema of the program's inputs does after checking their types
+		let program_args = manifest.schema().encode(&json!({ "a": arg_1, "b": arg_2 })).unwrap();
 
 		// The reward for the correct proof submission
 		let reward: u128 = 42_000_000_000_000;
 
+		// The request's reward is returned if nobody proves the program before the deadline
+		let current_block = api
+			.rpc()
+			.header(None)
+			.await
+			.unwrap()
+			.expect("Latest header should exist")
+			.number;
+		let deadline = current_block + args.request_lifetime;
+
 		println!(
 			"Requesting proof of with program args: {:?}, {:?} and reward amount: {:?}",
 			arg_1, arg_2, reward
@@ -75,7 +94,7 @@ async fn main() {
 
 		api.tx()
 			.sign_and_submit_then_watch_default(
-				&substrate_node::tx().prover_mgmt().request_proof(FACTORS_ID, args, reward),
+				&prover_mgmt::request_proof(FACTORS_ID, program_args, reward, deadline, None),
 				&signer,
 			)
 			.await
@@ -0,0 +1,78 @@
+//! Static bindings to the calls of the `ProverMgmt` pallet used by this example.
+//!
+//! These mirror the pallet's current call signatures, so that the example does not depend on
+//! metadata generated from a running node. Payloads are not validated against the node's
+//! metadata: a mismatch is reported when the transaction is rejected.
+
+use codec::Encode;
+use subxt::tx::StaticTxPayload;
+
+const PALLET: &str = "ProverMgmt";
+
+pub type ImageId = [u32; 8];
+
+#[derive(Encode)]
+pub struct UploadProgram {
+	pub image_id: ImageId,
+	pub program: Vec<u8>,
+	pub name: Vec<u8>,
+	pub description: Vec<u8>,
+	pub source_hash: Option<[u8; 32]>,
+	pub manifest: Option<Vec<u8>>,
+}
+
+pub fn upload_program(
+	image_id: ImageId,
+	program: Vec<u8>,
+	name: Vec<u8>,
+	description: Vec<u8>,
+	source_hash: Option<[u8; 32]>,
+	manifest: Option<Vec<u8>>,
+) -> StaticTxPayload<UploadProgram> {
+	StaticTxPayload::new(
+		PALLET,
+		"upload_program",
+		UploadProgram { image_id, program, name, description, source_hash, manifest },
+		[0; 32],
+	)
+	.unvalidated()
+}
+
+/// A SCALE-encoded `RuntimeCall`, dispatched once a request is fulfilled
+pub struct EncodedCall(pub Vec<u8>);
+
+impl Encode for EncodedCall {
+	fn size_hint(&self) -> usize {
+		self.0.len()
+	}
+
+	// The call is already encoded, so it is written as is rather than as a byte vector
+	fn encode_to<W: codec::Output + ?Sized>(&self, dest: &mut W) {
+		dest.write(&self.0)
+	}
+}
+
+#[derive(Encode)]
+pub struct RequestProof {
+	pub image_id: ImageId,
+	pub args: Vec<Vec<u32>>,
+	pub reward: u128,
+	pub deadline: u32,
+	pub callback: Option<EncodedCall>,
+}
+
+pub fn request_proof(
+	image_id: ImageId,
+	args: Vec<Vec<u32>>,
+	reward: u128,
+	deadline: u32,
+	callback: Option<EncodedCall>,
+) -> StaticTxPayload<RequestProof> {
+	StaticTxPayload::new(
+		PALLET,
+		"request_proof",
+		RequestProof { image_id, args, reward, deadline, callback },
+		[0; 32],
+	)
+	.unvalidated()
+}
@@ -15,7 +15,14 @@
 #![no_main]
 #![no_std]
 
-use risc0_zkvm::guest::env;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use risc0_zkvm::{
+	guest::env,
+	serde::to_vec,
+	sha::{Impl, Sha256},
+};
 
 risc0_zkvm::guest::entry!(main);
 
@@ -24,6 +31,11 @@ pub fn main() {
 	let a: u64 = env::read();
 	// Load the second number from the host
 	let b: u64 = env::read();
+	// Commit to the inputs first, so that the proof can only fulfill a request with these args
+	let mut input = to_vec(&a).expect("Serializable input");
+	input.extend(to_vec(&b).expect("Serializable input"));
+	let input_bytes: Vec<u8> = input.iter().flat_map(|word| word.to_le_bytes()).collect();
+	env::commit_slice(Impl::hash_bytes(&input_bytes).as_bytes());
 	// Verify that neither of them are 1 (i.e. nontrivial factors)
 	if a == 1 || b == 1 {
 		panic!("Trivial factors")
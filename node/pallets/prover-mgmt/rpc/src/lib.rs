@@ -0,0 +1,302 @@
+//! RPC interface for the proof market of the prover-mgmt pallet.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Codec;
+use futures::{future, stream, FutureExt, StreamExt};
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+	types::{
+		error::{CallError, ErrorObject},
+		SubscriptionResult,
+	},
+	SubscriptionSink,
+};
+use sc_client_api::BlockchainEvents;
+use sc_rpc::SubscriptionTaskExecutor;
+use sc_rpc_api::DenyUnsafe;
+use serde::{de::DeserializeOwned, Serialize};
+use sp_api::{ApiError, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::{traits::Block as BlockT, DispatchError};
+
+pub use pallet_prover_mgmt_runtime_api::ProverMgmtApi as ProverMgmtRuntimeApi;
+use pallet_prover_mgmt_runtime_api::{
+	ImageId, ProgramDetails, ProofDetails, ProofId, ProverDetails, RequestDetails, RequestId,
+};
+
+/// Number of requests returned by `prover_openRequests` if no limit is given
+const DEFAULT_REQUESTS_LIMIT: u32 = 100;
+/// Maximum number of requests returned by a single `prover_openRequests` call
+const MAX_REQUESTS_LIMIT: u32 = 1000;
+
+const LOG_TARGET: &str = "rpc::prover";
+
+#[rpc(client, server, namespace = "prover")]
+pub trait ProverApi<BlockHash, AccountId, Balance, BlockNumber> {
+	/// Open requests with an id of at least `from`, in order of their ids, optionally only those
+	/// for `image_id`. Pass the id following the last returned request as `from` to get the next
+	/// page.
+	#[method(name = "openRequests")]
+	fn open_requests(
+		&self,
+		image_id: Option<ImageId>,
+		from: Option<RequestId>,
+		limit: Option<u32>,
+		at: Option<BlockHash>,
+	) -> RpcResult<Vec<RequestDetails<AccountId, Balance, BlockNumber>>>;
+
+	/// Owner and metadata of an uploaded program
+	#[method(name = "program")]
+	fn program(
+		&self,
+		image_id: ImageId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ProgramDetails<AccountId, Balance, BlockNumber>>>;
+
+	/// A stored proof, along with its journal
+	#[method(name = "proof")]
+	fn proof(
+		&self,
+		proof_id: ProofId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ProofDetails<AccountId, BlockNumber>>>;
+
+	/// The stake, capabilities and status of a registered prover
+	#[method(name = "proverInfo")]
+	fn prover_info(
+		&self,
+		prover: AccountId,
+		at: Option<BlockHash>,
+	) -> RpcResult<Option<ProverDetails<Balance, BlockNumber>>>;
+
+	/// Verify a receipt without submitting a transaction. This is an unsafe method, as
+	/// verification is expensive.
+	#[method(name = "verifyReceipt")]
+	fn verify_receipt(
+		&self,
+		image_id: ImageId,
+		receipt_data: Vec<(Vec<u32>, u32)>,
+		journal: Bytes,
+		at: Option<BlockHash>,
+	) -> RpcResult<Result<(), DispatchError>>;
+
+	/// Stream the requests opened from now on, optionally only those for `image_id`, as the
+	/// blocks opening them are imported
+	#[subscription(
+		name = "subscribeRequests" => "request",
+		unsubscribe = "unsubscribeRequests",
+		item = RequestDetails<AccountId, Balance, BlockNumber>,
+	)]
+	fn subscribe_requests(&self, image_id: Option<ImageId>);
+}
+
+/// Provides RPC methods to query the proof market.
+pub struct Prover<C, Block> {
+	client: Arc<C>,
+	deny_unsafe: DenyUnsafe,
+	executor: SubscriptionTaskExecutor,
+	_marker: PhantomData<Block>,
+}
+
+impl<C, Block> Prover<C, Block> {
+	/// Create a new instance of the `Prover` RPC handler.
+	pub fn new(
+		client: Arc<C>,
+		deny_unsafe: DenyUnsafe,
+		executor: SubscriptionTaskExecutor,
+	) -> Self {
+		Self { client, deny_unsafe, executor, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i32 {
+	fn from(e: Error) -> i32 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+fn runtime_error(message: &str, error: impl std::fmt::Debug) -> JsonRpseeError {
+	CallError::Custom(ErrorObject::owned(
+		Error::RuntimeError.into(),
+		message,
+		Some(format!("{:?}", error)),
+	))
+	.into()
+}
+
+/// Open requests with an id of at least `from`, fetched from the runtime in pages of at most
+/// `MAX_REQUESTS_LIMIT` requests
+fn all_open_requests<C, Block, AccountId, Balance, BlockNumber>(
+	client: &C,
+	at: Block::Hash,
+	image_id: Option<ImageId>,
+	mut from: RequestId,
+) -> Result<Vec<RequestDetails<AccountId, Balance, BlockNumber>>, ApiError>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block>,
+	C::Api: ProverMgmtRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	AccountId: Codec,
+	Balance: Codec,
+	BlockNumber: Codec,
+{
+	let mut requests = Vec::new();
+	loop {
+		let page = client.runtime_api().open_requests(at, image_id, from, MAX_REQUESTS_LIMIT)?;
+		let is_last_page = (page.len() as u32) < MAX_REQUESTS_LIMIT;
+		if let Some(request) = page.last() {
+			from = request.request_id + 1;
+		}
+		requests.extend(page);
+		if is_last_page {
+			return Ok(requests)
+		}
+	}
+}
+
+impl<C, Block, AccountId, Balance, BlockNumber>
+	ProverApiServer<<Block as BlockT>::Hash, AccountId, Balance, BlockNumber> for Prover<C, Block>
+where
+	Block: BlockT,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>,
+	C: Send + Sync + 'static,
+	C::Api: ProverMgmtRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	AccountId: Codec + Serialize + DeserializeOwned + Send + Sync + 'static,
+	Balance: Codec + Serialize + Send + Sync + 'static,
+	BlockNumber: Codec + Serialize + Send + Sync + 'static,
+{
+	fn open_requests(
+		&self,
+		image_id: Option<ImageId>,
+		from: Option<RequestId>,
+		limit: Option<u32>,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Vec<RequestDetails<AccountId, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+		let limit = limit.unwrap_or(DEFAULT_REQUESTS_LIMIT).min(MAX_REQUESTS_LIMIT);
+
+		api.open_requests(at_hash, image_id, from.unwrap_or_default(), limit)
+			.map_err(|e| runtime_error("Unable to query open requests.", e))
+	}
+
+	fn program(
+		&self,
+		image_id: ImageId,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<ProgramDetails<AccountId, Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		api.program_info(at_hash, image_id)
+			.map_err(|e| runtime_error("Unable to query program.", e))
+	}
+
+	fn proof(
+		&self,
+		proof_id: ProofId,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<ProofDetails<AccountId, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		api.proof(at_hash, proof_id)
+			.map_err(|e| runtime_error("Unable to query proof.", e))
+	}
+
+	fn prover_info(
+		&self,
+		prover: AccountId,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Option<ProverDetails<Balance, BlockNumber>>> {
+		let api = self.client.runtime_api();
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		api.prover(at_hash, prover)
+			.map_err(|e| runtime_error("Unable to query prover.", e))
+	}
+
+	fn verify_receipt(
+		&self,
+		image_id: ImageId,
+		receipt_data: Vec<(Vec<u32>, u32)>,
+		journal: Bytes,
+		at: Option<Block::Hash>,
+	) -> RpcResult<Result<(), DispatchError>> {
+		self.deny_unsafe.check_if_safe()?;
+		let api = self.client.runtime_api();
+		let at_hash = at.unwrap_or_else(|| self.client.info().best_hash);
+
+		api.verify_receipt(at_hash, image_id, receipt_data, journal.to_vec())
+			.map_err(|e| runtime_error("Unable to verify receipt.", e))
+	}
+
+	fn subscribe_requests(
+		&self,
+		mut sink: SubscriptionSink,
+		image_id: Option<ImageId>,
+	) -> SubscriptionResult {
+		// Requests are streamed in order of their ids, starting with those made after the best
+		// block. Each imported block only reads the ids assigned since the previous one.
+		let best_hash = self.client.info().best_hash;
+		let cursor = match self.client.runtime_api().next_request_id(best_hash) {
+			Ok(next_request_id) => next_request_id,
+			Err(e) => return sink.reject(runtime_error("Unable to query the next request id.", e)),
+		};
+
+		let client = self.client.clone();
+		let stream = self
+			.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.scan(cursor, move |cursor, notification| {
+				// Requests missed because of an error are picked up with the next block, unless
+				// they were closed in the meantime
+				let requests = all_open_requests::<_, Block, AccountId, Balance, BlockNumber>(
+					&*client,
+					notification.hash,
+					image_id,
+					*cursor,
+				)
+				.and_then(|requests| {
+					let next_request_id =
+						client.runtime_api().next_request_id(notification.hash)?;
+					Ok((requests, next_request_id))
+				});
+				let requests = match requests {
+					Ok((requests, next_request_id)) => {
+						*cursor = next_request_id;
+						requests
+					},
+					Err(e) => {
+						log::warn!(
+							target: LOG_TARGET,
+							"Unable to query open requests at {:?}: {:?}",
+							notification.hash,
+							e
+						);
+						Vec::new()
+					},
+				};
+				future::ready(Some(stream::iter(requests)))
+			})
+			.flatten();
+
+		let fut = async move {
+			sink.pipe_from_stream(stream).await;
+		};
+		self.executor.spawn("prover-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
+}
@@ -0,0 +1,55 @@
+//! Runtime API through which clients query the prover-mgmt pallet without knowing its storage
+//! layout.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_runtime::DispatchResult;
+use sp_std::vec::Vec;
+
+pub use pallet_prover_mgmt::{
+	ImageId, ProgramDetails, ProofDetails, ProofId, ProverDetails, RequestDetails, RequestId,
+};
+
+sp_api::decl_runtime_apis! {
+	pub trait ProverMgmtApi<AccountId, Balance, BlockNumber> where
+		AccountId: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// Open requests with an id of at least `from`, in order of their ids, optionally only
+		/// those for `image_id`. At most `limit` requests are returned. The cost grows with the
+		/// number of requests made since `from`, whether they are still open or not.
+		fn open_requests(
+			image_id: Option<ImageId>,
+			from: RequestId,
+			limit: u32,
+		) -> Vec<RequestDetails<AccountId, Balance, BlockNumber>>;
+
+		/// The id the next request will be assigned, from which requests made after this block
+		/// are listed
+		fn next_request_id() -> RequestId;
+
+		/// Owner and metadata of an uploaded program
+		fn program_info(image_id: ImageId) -> Option<ProgramDetails<AccountId, Balance, BlockNumber>>;
+
+		/// The bincode-serialized ELF of an uploaded program
+		fn program(image_id: ImageId) -> Option<Vec<u8>>;
+
+		/// A stored proof, along with its journal
+		fn proof(proof_id: ProofId) -> Option<ProofDetails<AccountId, BlockNumber>>;
+
+		/// The id of the stored proof which fulfilled a request
+		fn request_proof_id(request_id: RequestId) -> Option<ProofId>;
+
+		/// The stake and capabilities of a registered prover
+		fn prover(who: AccountId) -> Option<ProverDetails<Balance, BlockNumber>>;
+
+		/// Verify a receipt like `store_and_verify_proof` would, without submitting a transaction
+		fn verify_receipt(
+			image_id: ImageId,
+			receipt_data: Vec<(Vec<u32>, u32)>,
+			journal: Vec<u8>,
+		) -> DispatchResult;
+	}
+}
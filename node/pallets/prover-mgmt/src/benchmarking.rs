@@ -1 +1,557 @@
+//! Benchmarks for the prover-mgmt pallet.
+//!
+//! Proofs are submitted and verified with the receipt of `fixtures/factors.receipt`, so that the
+//! benchmarks measure the verification of a real receipt.
 
+use super::*;
+use crate::Pallet as ProverMgmt;
+use frame_benchmarking::v2::*;
+use frame_support::{
+	sp_runtime::traits::{Bounded, StaticLookup},
+	sp_std::{boxed::Box, vec, vec::Vec},
+	traits::{Currency, Get, Hooks},
+};
+use frame_system::RawOrigin;
+
+const SEED: u32 = 0;
+
+/// Give an account enough balance to pay for anything it is benchmarked with
+fn fund<T: Config>(who: &T::AccountId) {
+	T::Currency::make_free_balance_be(who, BalanceOf::<T>::max_value() / 2u32.into());
+}
+
+fn funded<T: Config>(name: &'static str, index: u32) -> T::AccountId {
+	let who = account(name, index, SEED);
+	fund::<T>(&who);
+	who
+}
+
+fn reward<T: Config>() -> BalanceOf<T> {
+	1_000u32.into()
+}
+
+/// As many arguments of the maximum length as a request may have
+fn max_args<T: Config>() -> Vec<Vec<u32>> {
+	vec![vec![u32::MAX; T::MaxArgsLength::get() as usize]; T::MaxArgs::get() as usize]
+}
+
+/// The latest deadline a request opened now may have
+fn max_deadline<T: Config>() -> T::BlockNumber {
+	frame_system::Pallet::<T>::block_number() + T::MaxRequestLifetime::get()
+}
+
+fn callback<T: Config>() -> Box<<T as Config>::RuntimeCall>
+where
+	<T as Config>::RuntimeCall: From<frame_system::Call<T>>,
+{
+	Box::new(frame_system::Call::<T>::remark { remark: vec![] }.into())
+}
+
+/// Upload a program under `image_id`, with a name, description and manifest of the maximum
+/// length. Returns its owner.
+fn upload<T: Config>(image_id: ImageId, program: Vec<u8>) -> T::AccountId {
+	let owner = funded::<T>("owner", 0);
+	ProverMgmt::<T>::upload_program(
+		RawOrigin::Signed(owner.clone()).into(),
+		image_id,
+		program,
+		vec![0; T::MaxNameLength::get() as usize],
+		vec![0; T::MaxDescriptionLength::get() as usize],
+		Some([0; 32]),
+		Some(vec![0; T::MaxManifestLength::get() as usize]),
+	)
+	.expect("The program fits within the bounds of the pallet");
+	owner
+}
+
+/// Open a request for a proof of `image_id`, returning its id
+fn request<T: Config>(
+	requester: &T::AccountId,
+	image_id: ImageId,
+	args: Vec<Vec<u32>>,
+	deadline: T::BlockNumber,
+	callback: Option<Box<<T as Config>::RuntimeCall>>,
+) -> RequestId {
+	let request_id = NextRequestId::<T>::get();
+	ProverMgmt::<T>::request_proof(
+		RawOrigin::Signed(requester.clone()).into(),
+		image_id,
+		args,
+		reward::<T>(),
+		deadline,
+		callback,
+	)
+	.expect("The request is valid");
+	request_id
+}
+
+/// Open an auction for a proof of `image_id`, returning its id
+fn auction<T: Config>(requester: &T::AccountId, image_id: ImageId) -> RequestId {
+	let request_id = NextRequestId::<T>::get();
+	ProverMgmt::<T>::request_proof_auction(
+		RawOrigin::Signed(requester.clone()).into(),
+		image_id,
+		vec![],
+		reward::<T>(),
+		max_deadline::<T>(),
+	)
+	.expect("The auction is valid");
+	request_id
+}
+
+fn register<T: Config>(prover: &T::AccountId) {
+	fund::<T>(prover);
+	ProverMgmt::<T>::register_prover(
+		RawOrigin::Signed(prover.clone()).into(),
+		T::MinProverStake::get(),
+		u64::MAX,
+		vec![0; T::MaxVersionLength::get() as usize],
+	)
+	.expect("The prover is funded");
+}
+
+fn commit_bid_of<T: Config>(prover: &T::AccountId, request_id: RequestId, price: BalanceOf<T>) {
+	ProverMgmt::<T>::commit_bid(
+		RawOrigin::Signed(prover.clone()).into(),
+		request_id,
+		ProverMgmt::<T>::bid_commitment(prover, price, &[0; 32]),
+	)
+	.expect("The auction takes bids");
+}
+
+fn reveal_bid_of<T: Config>(prover: &T::AccountId, request_id: RequestId, price: BalanceOf<T>) {
+	ProverMgmt::<T>::reveal_bid(
+		RawOrigin::Signed(prover.clone()).into(),
+		request_id,
+		price,
+		[0; 32],
+	)
+	.expect("The bid was committed");
+}
+
+/// A bincode-serialized ELF of about `len` bytes, whose single segment loads every byte after its
+/// headers into memory. Computing the image id of a program is the most expensive when all of
+/// it is loaded.
+fn program_of_length(len: u32) -> Vec<u8> {
+	// The ELF header and a single program header
+	const HEADERS_LEN: u32 = 52 + 32;
+	const LOAD_ADDR: u32 = 0x1_0000;
+	// Besides the ELF, bincode stores its length as a `u64`
+	let data_len = len.saturating_sub(HEADERS_LEN + 8) & !3;
+
+	// 32-bit little endian ELF, version 1
+	let mut elf = vec![0x7f, b'E', b'L', b'F', 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+	// Executable for RISC-V, entering at the loaded data, with its program headers right
+	// after the ELF header and no section headers
+	for half in [2u16, 243] {
+		elf.extend_from_slice(&half.to_le_bytes());
+	}
+	for word in [1u32, LOAD_ADDR, 52, 0, 0] {
+		elf.extend_from_slice(&word.to_le_bytes());
+	}
+	for half in [52u16, 32, 1, 40, 0, 0] {
+		elf.extend_from_slice(&half.to_le_bytes());
+	}
+	// Load the data right after the headers
+	for word in [1u32, HEADERS_LEN, LOAD_ADDR, LOAD_ADDR, data_len, data_len, 0b111, 4] {
+		elf.extend_from_slice(&word.to_le_bytes());
+	}
+	elf.resize((HEADERS_LEN + data_len) as usize, 0xff);
+
+	let mut program = (elf.len() as u64).to_le_bytes().to_vec();
+	program.extend(elf);
+	program
+}
+
+#[benchmarks(where <T as Config>::RuntimeCall: From<frame_system::Call<T>>)]
+mod benchmarks {
+	use super::*;
+
+	/// `l` is bounded by the `MaxProgramLength` of the runtime
+	#[benchmark]
+	fn upload_program(l: Linear<0, 65_536>) {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let image_id = fixtures::factors().image_id;
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			image_id,
+			vec![0; l as usize],
+			vec![0; T::MaxNameLength::get() as usize],
+			vec![0; T::MaxDescriptionLength::get() as usize],
+			Some([0; 32]),
+			Some(vec![0; T::MaxManifestLength::get() as usize]),
+		);
+
+		assert!(Programs::<T>::contains_key(image_id));
+	}
+
+	#[benchmark]
+	fn request_proof() {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let request_id = NextRequestId::<T>::get();
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			image_id,
+			max_args::<T>(),
+			reward::<T>(),
+			max_deadline::<T>(),
+			Some(callback::<T>()),
+		);
+
+		assert!(Callbacks::<T>::contains_key(request_id));
+	}
+
+	/// Fulfills a claimed request with a callback. The verification of the single segment of the
+	/// fixture is included, so that the weight of a submission counts it twice.
+	#[benchmark]
+	fn store_and_verify_proof() {
+		let fixture = fixtures::factors();
+		upload::<T>(fixture.image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let request_id = request::<T>(
+			&requester,
+			fixture.image_id,
+			fixtures::factors_args(),
+			max_deadline::<T>(),
+			Some(callback::<T>()),
+		);
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller);
+		ProverMgmt::<T>::claim_request(RawOrigin::Signed(caller.clone()).into(), request_id)
+			.expect("The request is open");
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller),
+			fixture.image_id,
+			Some(request_id),
+			fixture.receipt_data,
+			fixture.journal,
+		);
+
+		assert!(FulfilledRequests::<T>::contains_key(request_id));
+	}
+
+	#[benchmark]
+	fn cancel_request() {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let request_id = request::<T>(
+			&caller,
+			image_id,
+			max_args::<T>(),
+			max_deadline::<T>(),
+			Some(callback::<T>()),
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), request_id);
+
+		assert!(!ProofRequests::<T>::contains_key(request_id));
+	}
+
+	/// `n` requests with a callback expire in the same block
+	#[benchmark]
+	fn on_initialize_expire_requests(n: Linear<0, 50>) {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let deadline = max_deadline::<T>();
+		for _ in 0..n {
+			request::<T>(&requester, image_id, vec![], deadline, Some(callback::<T>()));
+		}
+
+		#[block]
+		{
+			ProverMgmt::<T>::on_initialize(deadline);
+		}
+
+		assert_eq!(ProofRequests::<T>::iter().count(), 0);
+	}
+
+	#[benchmark]
+	fn remove_program() {
+		let image_id = fixtures::factors().image_id;
+		let owner = upload::<T>(image_id, vec![0; T::MaxProgramLength::get() as usize]);
+		frame_system::Pallet::<T>::set_block_number(
+			frame_system::Pallet::<T>::block_number() + T::ChallengePeriod::get(),
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(owner), image_id);
+
+		assert!(!Programs::<T>::contains_key(image_id));
+	}
+
+	#[benchmark]
+	fn transfer_program_ownership() {
+		let image_id = fixtures::factors().image_id;
+		let owner = upload::<T>(image_id, vec![]);
+		let new_owner = funded::<T>("new_owner", 0);
+		frame_system::Pallet::<T>::set_block_number(
+			frame_system::Pallet::<T>::block_number() + T::ChallengePeriod::get(),
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(owner), image_id, T::Lookup::unlookup(new_owner.clone()));
+
+		assert_eq!(ProgramInfos::<T>::get(image_id).and_then(|info| info.owner), Some(new_owner));
+	}
+
+	/// A successful challenge of a valid ELF of `l` bytes, which is loaded entirely. `l` is
+	/// bounded by the `MaxProgramLength` of the runtime.
+	#[benchmark]
+	fn challenge_image_id(l: Linear<0, 65_536>) {
+		// No program has an image id of all zeroes
+		let image_id = [0; 8];
+		upload::<T>(image_id, program_of_length(l));
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), image_id);
+
+		assert!(!Programs::<T>::contains_key(image_id));
+	}
+
+	#[benchmark]
+	fn register_prover() {
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+
+		#[extrinsic_call]
+		_(
+			RawOrigin::Signed(caller.clone()),
+			T::MinProverStake::get(),
+			u64::MAX,
+			vec![0; T::MaxVersionLength::get() as usize],
+		);
+
+		assert!(ProverMgmt::<T>::is_active_prover(&caller));
+	}
+
+	#[benchmark]
+	fn unregister_prover() {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()));
+
+		assert!(!ProverMgmt::<T>::is_active_prover(&caller));
+	}
+
+	#[benchmark]
+	fn withdraw_unbonded() {
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller);
+		ProverMgmt::<T>::unregister_prover(RawOrigin::Signed(caller.clone()).into())
+			.expect("The prover is registered");
+		frame_system::Pallet::<T>::set_block_number(
+			frame_system::Pallet::<T>::block_number() + T::UnbondingPeriod::get(),
+		);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()));
+
+		assert!(!Provers::<T>::contains_key(&caller));
+	}
+
+	#[benchmark]
+	fn claim_request() {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let request_id = request::<T>(&requester, image_id, vec![], max_deadline::<T>(), None);
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), request_id);
+
+		assert_eq!(ClaimExpiries::<T>::iter().count(), 1);
+	}
+
+	/// The claims of `n` provers on as many requests lapse in the same block
+	#[benchmark]
+	fn on_initialize_lapse_claims(n: Linear<0, 50>) {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let deadline = max_deadline::<T>();
+		for i in 0..n {
+			let request_id = request::<T>(&requester, image_id, vec![], deadline, None);
+			let prover = account("prover", i, SEED);
+			register::<T>(&prover);
+			ProverMgmt::<T>::claim_request(RawOrigin::Signed(prover).into(), request_id)
+				.expect("The request is open");
+		}
+		let expires_at = frame_system::Pallet::<T>::block_number() + T::ClaimWindow::get();
+
+		#[block]
+		{
+			ProverMgmt::<T>::on_initialize(expires_at);
+		}
+
+		assert_eq!(ClaimExpiries::<T>::iter().count(), 0);
+	}
+
+	#[benchmark]
+	fn request_proof_auction() {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let caller: T::AccountId = whitelisted_caller();
+		fund::<T>(&caller);
+		let request_id = NextRequestId::<T>::get();
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), image_id, max_args::<T>(), reward::<T>(), max_deadline::<T>());
+
+		assert!(Auctions::<T>::contains_key(request_id));
+	}
+
+	#[benchmark]
+	fn commit_bid() {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let request_id = auction::<T>(&requester, image_id);
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller);
+		let commitment = ProverMgmt::<T>::bid_commitment(&caller, reward::<T>(), &[0; 32]);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller.clone()), request_id, commitment);
+
+		assert!(Bids::<T>::contains_key(request_id, &caller));
+	}
+
+	/// Outbids the lowest bid revealed so far, whose deposit is returned
+	#[benchmark]
+	fn reveal_bid() {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let request_id = auction::<T>(&requester, image_id);
+		let outbid = funded::<T>("prover", 0);
+		register::<T>(&outbid);
+		let caller: T::AccountId = whitelisted_caller();
+		register::<T>(&caller);
+		let price = reward::<T>() / 2u32.into();
+		commit_bid_of::<T>(&outbid, request_id, reward::<T>());
+		commit_bid_of::<T>(&caller, request_id, price);
+		frame_system::Pallet::<T>::set_block_number(
+			frame_system::Pallet::<T>::block_number() + T::BiddingPeriod::get(),
+		);
+		reveal_bid_of::<T>(&outbid, request_id, reward::<T>());
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(caller), request_id, price, [0; 32]);
+
+		assert!(Bids::<T>::iter_prefix(request_id).next().is_none());
+	}
+
+	/// `n` auctions close in the same block, each won by a revealed bid, and `b` bids which were
+	/// never revealed are forfeited. Along with the winning bid, `b` is bounded by the `MaxBids`
+	/// of the runtime.
+	#[benchmark]
+	fn on_initialize_close_auctions(n: Linear<1, 50>, b: Linear<0, 31>) {
+		let image_id = fixtures::factors().image_id;
+		upload::<T>(image_id, vec![]);
+		let requester = funded::<T>("requester", 0);
+		let winner = funded::<T>("winner", 0);
+		register::<T>(&winner);
+		let request_ids: Vec<_> = (0..n).map(|_| auction::<T>(&requester, image_id)).collect();
+		for &request_id in &request_ids {
+			commit_bid_of::<T>(&winner, request_id, reward::<T>());
+		}
+		// The unrevealed bids are all made on the first auction
+		for i in 0..b {
+			let prover = account("prover", i, SEED);
+			register::<T>(&prover);
+			commit_bid_of::<T>(&prover, request_ids[0], reward::<T>());
+		}
+
+		let now = frame_system::Pallet::<T>::block_number();
+		frame_system::Pallet::<T>::set_block_number(now + T::BiddingPeriod::get());
+		for &request_id in &request_ids {
+			reveal_bid_of::<T>(&winner, request_id, reward::<T>());
+		}
+		let reveal_end = now + T::BiddingPeriod::get() + T::RevealPeriod::get();
+
+		#[block]
+		{
+			ProverMgmt::<T>::on_initialize(reveal_end);
+		}
+
+		assert_eq!(Auctions::<T>::iter().count(), 0);
+	}
+
+	/// `n` proofs are no longer kept by `ProofPruning`. Pruning is free when all proofs are kept.
+	#[benchmark]
+	fn on_initialize_prune_proofs(n: Linear<0, 50>) -> Result<(), BenchmarkError> {
+		let now = frame_system::Pallet::<T>::block_number();
+		// The proofs which are kept, and the block from which the oldest `n` proofs are pruned
+		let (kept, pruned_at) = match T::ProofPruning::get() {
+			PruningPolicy::KeepAll => return Err(BenchmarkError::Weightless),
+			PruningPolicy::KeepLastOverall(count) => (count, now),
+			PruningPolicy::KeepFor(period) => (0, now + period),
+		};
+
+		let fixture = fixtures::factors();
+		let prover: T::AccountId = account("prover", 0, SEED);
+		for request_id in 0..n + kept {
+			ProverMgmt::<T>::store_proof(StoredProof {
+				image_id: fixture.image_id,
+				receipt: ProverMgmt::<T>::bound_receipt(fixture.receipt_data.clone())
+					.map_err(|_| "Receipt too large")?,
+				journal: fixture.journal.clone().try_into().map_err(|_| "Journal too long")?,
+				prover: prover.clone(),
+				request: Some(request_id.into()),
+				verified_at: now,
+			})?;
+		}
+
+		#[block]
+		{
+			ProverMgmt::<T>::on_initialize(pruned_at);
+		}
+
+		let pruned = n.min(T::MaxProofsPrunedPerBlock::get());
+		assert_eq!(OldestProofId::<T>::get(), ProofId::from(pruned));
+		Ok(())
+	}
+
+	/// Segment receipts are verified one after the other, so a receipt of `s` segments is as
+	/// expensive to verify as `s` receipts of a single segment. `s` is bounded by the
+	/// `MaxSegments` of the runtime.
+	#[benchmark]
+	fn verify_receipt(s: Linear<1, 12>) {
+		let fixture = fixtures::factors();
+		let receipts = vec![fixture; s as usize];
+
+		#[block]
+		{
+			for fixture in receipts {
+				<ProverMgmt<T> as ProofVerifier>::verify_receipt(
+					fixture.image_id,
+					fixture.receipt_data,
+					fixture.journal,
+				)
+				.expect("The fixture is a valid receipt");
+			}
+		}
+	}
+
+	impl_benchmark_test_suite!(ProverMgmt, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,39 @@
+//! A real receipt for tests and benchmarks, so that they verify proofs instead of stubbing the
+//! verifier out.
+//!
+//! `fixtures/factors.receipt` holds the SCALE-encoded `(image_id, receipt_data, journal)` args of
+//! `store_and_verify_proof` for a proof of the `factors` example with the args `17` and `23`. Its
+//! journal is the input commitment of those args followed by their product, `391`. It was proven
+//! with `risc0-zkvm` 0.15, and must be proven again whenever the verifier changes.
+//!
+//! `fixtures/factors.elf` is the bincode-serialized ELF of that `factors` example, as uploaded with
+//! `upload_program`, so that the image id of the fixture can be challenged.
+
+use crate::ImageId;
+use codec::Decode;
+use frame_support::sp_std::{vec, vec::Vec};
+
+/// A proof of the `factors` example, as passed to `store_and_verify_proof`
+#[derive(Clone, Decode)]
+pub struct Fixture {
+	pub image_id: ImageId,
+	pub receipt_data: Vec<(Vec<u32>, u32)>,
+	pub journal: Vec<u8>,
+}
+
+/// The args the `factors` fixture was proven with, as passed to `request_proof`
+pub fn factors_args() -> Vec<Vec<u32>> {
+	// The risc0 serialization of two `u64`s
+	vec![vec![17, 0], vec![23, 0]]
+}
+
+pub fn factors() -> Fixture {
+	Fixture::decode(&mut &include_bytes!("../fixtures/factors.receipt")[..])
+		.expect("The fixture is a valid encoding")
+}
+
+/// The program the `factors` fixture was proven with, as passed to `upload_program`
+#[cfg(test)]
+pub fn factors_program() -> Vec<u8> {
+	include_bytes!("../fixtures/factors.elf").to_vec()
+}
@@ -13,29 +13,94 @@ mod tests;
 
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+mod fixtures;
+pub mod migrations;
+pub mod traits;
+pub mod types;
 pub mod weights;
+pub use traits::{OnProofVerified, ProofVerifier};
+pub use types::*;
 pub use weights::*;
 
+pub(crate) const LOG_TARGET: &str = "runtime::prover-mgmt";
+
+/// Host functions which are too heavy to run inside of the runtime. Nodes must register
+/// `program_image::HostFunctions` with their executor.
+#[sp_runtime_interface::runtime_interface]
+pub trait ProgramImage {
+	/// Compute the image id of a bincode-serialized RISC Zero ELF, or `None` if it is not a valid
+	/// program
+	fn compute_image_id(program: &[u8]) -> Option<[u32; 8]> {
+		use risc0_zkvm::{MemoryImage, Program};
+		use risc0_zkvm_platform::{memory::MEM_SIZE, PAGE_SIZE};
+
+		let elf: Vec<u8> = bincode::deserialize(program).ok()?;
+		let program = Program::load_elf(&elf, MEM_SIZE as u32).ok()?;
+		let image = MemoryImage::new(&program, PAGE_SIZE as u32);
+		image.compute_id().as_words().try_into().ok()
+	}
+}
+
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
 	use frame_support::{
+		dispatch::{GetDispatchInfo, PostDispatchInfo},
 		inherent::Vec,
 		pallet_prelude::*,
-		traits::{BalanceStatus, Currency, ReservableCurrency},
+		sp_io::hashing::{blake2_256, sha2_256},
+		sp_runtime::{
+			traits::{Dispatchable, Saturating, StaticLookup, Zero},
+			ArithmeticError, Perbill,
+		},
+		sp_std::boxed::Box,
+		storage::with_storage_layer,
+		traits::{BalanceStatus, Currency, EnsureOrigin, ReservableCurrency},
+		weights::WeightToFee,
 	};
 	use frame_system::pallet_prelude::*;
 	use risc0_zkvm::{SegmentReceipt, SessionReceipt};
 
-	type ImageId = [u32; 8];
+	pub type ImageId = [u32; 8];
+	/// Unique identifier assigned to every proof request
+	pub type RequestId = u64;
+	/// Unique identifier assigned to every verified proof
+	pub type ProofId = u64;
+
+	/// Which verified proofs are kept in storage
+	#[derive(Clone, Copy, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	pub enum PruningPolicy<BlockNumber> {
+		/// Never prune proofs
+		KeepAll,
+		/// Keep only the given number of most recent proofs, across all programs. A program with
+		/// frequent proofs can push the proofs of other programs out of storage.
+		KeepLastOverall(u32),
+		/// Keep proofs for the given number of blocks after they were verified
+		KeepFor(BlockNumber),
+	}
+
+	/// The current storage version.
+	const STORAGE_VERSION: StorageVersion = StorageVersion::new(5);
 
 	#[pallet::pallet]
-	// TODO: Needs proper BoundedVec encoding from offchain in order to get bounded types working
-	#[pallet::without_storage_info]
+	#[pallet::storage_version(STORAGE_VERSION)]
 	pub struct Pallet<T>(_);
 
 	pub type BalanceOf<T> =
 		<<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+	/// A bincode-serialized program
+	pub type ProgramOf<T> = BoundedVec<u8, <T as Config>::MaxProgramLength>;
+	/// A single risc0-serialized argument passed to a program
+	pub type ArgOf<T> = BoundedVec<u32, <T as Config>::MaxArgsLength>;
+	/// All of the arguments passed to a program
+	pub type ArgsOf<T> = BoundedVec<ArgOf<T>, <T as Config>::MaxArgs>;
+	/// The seal of a single segment receipt
+	pub type SealOf<T> = BoundedVec<u32, <T as Config>::MaxProofLength>;
+	/// The seals and indexes of every segment receipt of a proof
+	pub type ReceiptOf<T> = BoundedVec<(SealOf<T>, u32), <T as Config>::MaxSegments>;
+	/// The public output committed by a program
+	pub type JournalOf<T> = BoundedVec<u8, <T as Config>::MaxJournalLength>;
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
@@ -44,51 +109,564 @@ pub mod pallet {
 			+ ReservableCurrency<Self::AccountId>;
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
 		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+		/// The runtime's origin, which the callbacks of requests are dispatched with
+		type RuntimeOrigin: From<RawOrigin>;
+		/// The runtime's call, of which the callbacks of requests are
+		type RuntimeCall: Parameter
+			+ Dispatchable<
+				RuntimeOrigin = <Self as Config>::RuntimeOrigin,
+				PostInfo = PostDispatchInfo,
+			> + GetDispatchInfo;
 		/// Type representing the weight of this pallet
 		type WeightInfo: WeightInfo;
+		// Max length of a single program argument
+		#[pallet::constant]
 		type MaxArgsLength: Get<u32>;
+		/// Maximum number of arguments passed to a program
+		#[pallet::constant]
+		type MaxArgs: Get<u32>;
 		// Max length of programs
+		#[pallet::constant]
 		type MaxProgramLength: Get<u32>;
 		// Max Length of proofs
+		#[pallet::constant]
 		type MaxProofLength: Get<u32>;
+		/// Maximum length of the journal of a proof
+		#[pallet::constant]
+		type MaxJournalLength: Get<u32>;
+		/// Maximum number of segment receipts in a proof
+		#[pallet::constant]
+		type MaxSegments: Get<u32>;
+		/// Maximum number of blocks a proof request may stay open for
+		#[pallet::constant]
+		type MaxRequestLifetime: Get<Self::BlockNumber>;
+		/// Maximum number of proof requests which may expire in the same block
+		#[pallet::constant]
+		type MaxExpiringPerBlock: Get<u32>;
+		/// The base deposit reserved from the uploader of a program
+		#[pallet::constant]
+		type DepositBase: Get<BalanceOf<Self>>;
+		/// The deposit reserved from the uploader of a program for each byte of the program
+		#[pallet::constant]
+		type DepositPerByte: Get<BalanceOf<Self>>;
+		/// Maximum length of the human-readable name of a program
+		#[pallet::constant]
+		type MaxNameLength: Get<u32>;
+		/// Maximum length of the human-readable description of a program
+		#[pallet::constant]
+		type MaxDescriptionLength: Get<u32>;
+		/// Maximum length of the manifest of a program
+		#[pallet::constant]
+		type MaxManifestLength: Get<u32>;
+		/// Number of blocks after an upload during which anyone may prove that the program does
+		/// not match its claimed image id
+		#[pallet::constant]
+		type ChallengePeriod: Get<Self::BlockNumber>;
+		/// Minimum stake a prover must bond to register
+		#[pallet::constant]
+		type MinProverStake: Get<BalanceOf<Self>>;
+		/// Number of blocks the stake of a prover stays bonded after it unregisters
+		#[pallet::constant]
+		type UnbondingPeriod: Get<Self::BlockNumber>;
+		/// Maximum length of the risc0 version a prover declares support for
+		#[pallet::constant]
+		type MaxVersionLength: Get<u32>;
+		/// Number of blocks a prover has to fulfill a request it claimed
+		#[pallet::constant]
+		type ClaimWindow: Get<Self::BlockNumber>;
+		/// Collateral reserved from a prover claiming a request, given to the requester if the
+		/// prover does not fulfill the request in time
+		#[pallet::constant]
+		type ClaimCollateral: Get<BalanceOf<Self>>;
+		/// Share of the stake of a prover given to the requester, on top of the claim collateral,
+		/// when a claim of the prover lapses. A prover whose stake falls below `MinProverStake`
+		/// can no longer claim or fulfill requests.
+		#[pallet::constant]
+		type LapseSlash: Get<Perbill>;
+		/// Number of blocks during which registered provers may commit sealed bids on an auctioned
+		/// request
+		#[pallet::constant]
+		type BiddingPeriod: Get<Self::BlockNumber>;
+		/// Number of blocks after the bidding period during which committed bids may be revealed
+		#[pallet::constant]
+		type RevealPeriod: Get<Self::BlockNumber>;
+		/// Deposit reserved for each sealed bid. It is returned once the bid is revealed and
+		/// outbid, held as claim collateral if the bid wins, and given to the requester if the bid
+		/// is never revealed.
+		#[pallet::constant]
+		type BidDeposit: Get<BalanceOf<Self>>;
+		/// Maximum number of bids committed on a single auctioned request
+		#[pallet::constant]
+		type MaxBids: Get<u32>;
+		/// Which verified proofs are kept in storage
+		#[pallet::constant]
+		type ProofPruning: Get<PruningPolicy<Self::BlockNumber>>;
+		/// Maximum number of proofs pruned in a single block
+		#[pallet::constant]
+		type MaxProofsPrunedPerBlock: Get<u32>;
+		/// Handler notified of every verified proof
+		type OnProofVerified: OnProofVerified<Self::AccountId>;
+		/// Converts the weight of a callback into the fee prepaid for it by the requester
+		type WeightToFee: WeightToFee<Balance = BalanceOf<Self>>;
+		/// Maximum length of the encoded callback of a request
+		#[pallet::constant]
+		type MaxCallbackLength: Get<u32>;
+		/// Maximum weight of the callback of a request
+		#[pallet::constant]
+		type MaxCallbackWeight: Get<Weight>;
+	}
+
+	type AccountIdLookupOf<T> = <<T as frame_system::Config>::Lookup as StaticLookup>::Source;
+
+	/// Origin of the callback of a request, dispatched once the request was fulfilled
+	#[derive(Clone, PartialEq, Eq, RuntimeDebug, Decode, Encode, TypeInfo, MaxEncodedLen)]
+	pub enum RawOrigin {
+		/// The callback of the request with the given id
+		Request(RequestId),
+	}
+
+	#[pallet::origin]
+	pub type Origin = RawOrigin;
+
+	/// Ensures that an origin is the callback of a request, yielding the id of the request
+	pub struct EnsureRequest;
+
+	impl<O: Into<Result<RawOrigin, O>> + From<RawOrigin>> EnsureOrigin<O> for EnsureRequest {
+		type Success = RequestId;
+
+		fn try_origin(o: O) -> Result<Self::Success, O> {
+			o.into().map(|RawOrigin::Request(request_id)| request_id)
+		}
+
+		#[cfg(feature = "runtime-benchmarks")]
+		fn try_successful_origin() -> Result<O, ()> {
+			Ok(O::from(RawOrigin::Request(Zero::zero())))
+		}
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// Information about an uploaded program
+	pub struct ProgramInfo<T: Config> {
+		// Account which owns the program, and from which the deposit is reserved. Programs
+		// uploaded before owners were recorded have none, and are managed by root.
+		pub(crate) owner: Option<T::AccountId>,
+		// Deposit paid for storing the program, returned when it is removed
+		pub(crate) deposit: BalanceOf<T>,
+		// Block in which the program was uploaded
+		pub(crate) uploaded_at: T::BlockNumber,
+		// Length of the bincode-serialized program, in bytes
+		pub(crate) size: u32,
+		// Human-readable name of the program
+		pub(crate) name: BoundedVec<u8, T::MaxNameLength>,
+		// Human-readable description of the program
+		pub(crate) description: BoundedVec<u8, T::MaxDescriptionLength>,
+		// Hash of the source code or repository revision the program was built from
+		pub(crate) source_hash: Option<[u8; 32]>,
+		// Until this block, anyone may challenge the claimed image id of the program
+		pub(crate) challenge_period_end: T::BlockNumber,
 	}
 
-	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo)]
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
 	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
 	// Information related to a requst for proving of a program
 	pub struct ProofRequest<T: Config> {
+		image_id: ImageId,
 		requester: T::AccountId,
 		reward: BalanceOf<T>,
-		args: Vec<Vec<u32>>,
+		args: ArgsOf<T>,
+		// Commitment to `args` which the guest must place at the start of its journal
+		input_commitment: [u8; 32],
+		// Block at which the request expires and its reward is returned, if not fulfilled
+		deadline: T::BlockNumber,
+		// Prover which currently has the exclusive right to fulfill the request, if any
+		claim: Option<Claim<T>>,
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// Call dispatched once a request is fulfilled
+	pub struct Callback<T: Config> {
+		// The SCALE-encoded `RuntimeCall`
+		call: BoundedVec<u8, T::MaxCallbackLength>,
+		weight: Weight,
+		// Reserved from the requester for `weight`, and paid to the prover which fulfills the
+		// request
+		fee: BalanceOf<T>,
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// Exclusive right of a prover to fulfill a request for a limited time
+	pub struct Claim<T: Config> {
+		prover: T::AccountId,
+		// Block at which the claim lapses if the request has not been fulfilled
+		expires_at: T::BlockNumber,
+		// Reserved from the prover, and given to the requester if the claim lapses
+		collateral: BalanceOf<T>,
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// State of the sealed-bid auction of a request
+	pub struct Auction<T: Config> {
+		// Block until which sealed bids may be committed
+		bidding_end: T::BlockNumber,
+		// Block until which committed bids may be revealed, at which point the auction closes
+		reveal_end: T::BlockNumber,
+		// Number of bids committed
+		bids: u32,
+		// Lowest valid bid revealed so far
+		lowest_bid: Option<RevealedBid<T>>,
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// A sealed bid which has not been revealed yet
+	pub struct SealedBid<T: Config> {
+		// See `Pallet::bid_commitment`
+		commitment: [u8; 32],
+		deposit: BalanceOf<T>,
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// A revealed bid, whose deposit stays reserved while it is the lowest
+	pub struct RevealedBid<T: Config> {
+		prover: T::AccountId,
+		price: BalanceOf<T>,
+		deposit: BalanceOf<T>,
 	}
 
 	#[pallet::storage]
 	/// Store for all programs
 	pub(super) type Programs<T: Config> =
-		StorageMap<_, Blake2_128Concat, ImageId, Vec<u8>, OptionQuery>;
+		StorageMap<_, Blake2_128Concat, ImageId, ProgramOf<T>, OptionQuery>;
+
+	#[pallet::storage]
+	/// Owner, storage deposit and metadata of each program
+	pub(super) type ProgramInfos<T: Config> =
+		StorageMap<_, Blake2_128Concat, ImageId, ProgramInfo<T>, OptionQuery>;
 
 	#[pallet::storage]
-	/// Requests which have been submitted for `ImageId`, unique per set of args + image id
+	/// Manifest describing the inputs and journal of each program which was uploaded with one.
+	/// Manifests are opaque to the chain, and are JSON-encoded `program_args::Manifest`s for the
+	/// client tooling of this repository.
+	pub(super) type ProgramManifests<T: Config> =
+		StorageMap<_, Blake2_128Concat, ImageId, BoundedVec<u8, T::MaxManifestLength>, OptionQuery>;
+
+	#[pallet::storage]
+	/// Id which will be assigned to the next proof request
+	pub(super) type NextRequestId<T: Config> = StorageValue<_, RequestId, ValueQuery>;
+
+	#[pallet::storage]
+	/// Open requests for proofs, by their unique request id
 	pub(super) type ProofRequests<T: Config> =
-		StorageMap<_, Blake2_128Concat, ImageId, ProofRequest<T>, OptionQuery>;
+		StorageMap<_, Twox64Concat, RequestId, ProofRequest<T>, OptionQuery>;
+
+	#[pallet::storage]
+	/// Callbacks of the open requests which have one
+	pub(super) type Callbacks<T: Config> =
+		StorageMap<_, Twox64Concat, RequestId, Callback<T>, OptionQuery>;
+
+	#[pallet::storage]
+	/// Index of the open requests for each `ImageId`
+	pub(super) type RequestsByImage<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, ImageId, Twox64Concat, RequestId, (), OptionQuery>;
+
+	#[pallet::storage]
+	/// Ids of the requests which expire at a given block
+	pub(super) type RequestExpiries<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<RequestId, T::MaxExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	/// Ids of the requests whose claim lapses at a given block
+	pub(super) type ClaimExpiries<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<RequestId, T::MaxExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	#[pallet::storage]
+	/// Auctions of requests which are still taking bids or reveals
+	pub(super) type Auctions<T: Config> =
+		StorageMap<_, Twox64Concat, RequestId, Auction<T>, OptionQuery>;
 
 	#[pallet::storage]
-	/// Store Some(proof), if the program's proof was verified
+	/// Sealed bids which have not been revealed yet, by request and bidder
+	pub(super) type Bids<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		RequestId,
+		Blake2_128Concat,
+		T::AccountId,
+		SealedBid<T>,
+		OptionQuery,
+	>;
+
+	#[pallet::storage]
+	/// Ids of the requests whose auction closes at a given block
+	pub(super) type AuctionCloses<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<RequestId, T::MaxExpiringPerBlock>,
+		ValueQuery,
+	>;
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// Settlement record of a request which was fulfilled by a verified proof
+	pub struct FulfilledRequest<T: Config> {
+		image_id: ImageId,
+		prover: T::AccountId,
+		reward: BalanceOf<T>,
+		fulfilled_at: T::BlockNumber,
+	}
+
+	#[pallet::storage]
+	/// Requests which have been fulfilled and settled. Request ids are never reused, so that a
+	/// request can not be paid twice even once its record is pruned along with its proof.
+	pub(super) type FulfilledRequests<T: Config> =
+		StorageMap<_, Twox64Concat, RequestId, FulfilledRequest<T>, OptionQuery>;
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// A verified proof along with the output of the execution it proves
+	pub struct StoredProof<T: Config> {
+		pub image_id: ImageId,
+		pub receipt: ReceiptOf<T>,
+		// Public output of the program, verified as part of the receipt
+		pub journal: JournalOf<T>,
+		// Account which submitted the proof
+		pub prover: T::AccountId,
+		// Request fulfilled by the proof, if any
+		pub request: Option<RequestId>,
+		pub verified_at: T::BlockNumber,
+	}
+
+	#[pallet::storage]
+	/// Id which will be assigned to the next verified proof
+	pub(super) type NextProofId<T: Config> = StorageValue<_, ProofId, ValueQuery>;
+
+	#[pallet::storage]
+	/// Id of the oldest proof which has not been pruned yet
+	pub(super) type OldestProofId<T: Config> = StorageValue<_, ProofId, ValueQuery>;
+
+	#[pallet::storage]
+	/// Verified proofs, by their unique proof id
+	pub(super) type Proofs<T: Config> =
+		StorageMap<_, Twox64Concat, ProofId, StoredProof<T>, OptionQuery>;
+
+	#[pallet::storage]
+	/// Index of the stored proofs of each `ImageId`
 	pub(super) type ProofsByImage<T: Config> =
-		StorageMap<_, Blake2_128Concat, ImageId, Vec<(Vec<u32>, u32)>, OptionQuery>;
+		StorageDoubleMap<_, Blake2_128Concat, ImageId, Twox64Concat, ProofId, (), OptionQuery>;
+
+	#[pallet::storage]
+	/// Index of the stored proofs submitted by each prover
+	pub(super) type ProofsByProver<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, ProofId, (), OptionQuery>;
+
+	#[pallet::storage]
+	/// The most recent stored proof of each `ImageId`
+	pub(super) type LatestProofByImage<T: Config> =
+		StorageMap<_, Blake2_128Concat, ImageId, ProofId, OptionQuery>;
+
+	#[pallet::storage]
+	/// The proof which fulfilled each request, while it is stored
+	pub(super) type ProofByRequest<T: Config> =
+		StorageMap<_, Twox64Concat, RequestId, ProofId, OptionQuery>;
+
+	#[pallet::storage]
+	/// The most recent stored proof of each `ImageId` with a given journal, keyed by the SHA-256
+	/// digest of the journal
+	pub(super) type ProofByJournal<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		ImageId,
+		Blake2_128Concat,
+		[u8; 32],
+		ProofId,
+		OptionQuery,
+	>;
+
+	#[derive(
+		CloneNoBound,
+		RuntimeDebugNoBound,
+		PartialEqNoBound,
+		EqNoBound,
+		Decode,
+		Encode,
+		TypeInfo,
+		MaxEncodedLen,
+	)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// What a prover declares itself able to prove
+	pub struct ProverCapabilities<T: Config> {
+		// Maximum number of cycles of a program execution the prover will prove
+		max_cycles: u64,
+		// Version of risc0 the prover proves with
+		risc0_version: BoundedVec<u8, T::MaxVersionLength>,
+	}
+
+	#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo, MaxEncodedLen)]
+	#[scale_info(skip_type_params(T))]
+	#[codec(mel_bound())]
+	// Information about a registered prover
+	pub struct ProverInfo<T: Config> {
+		// Stake reserved from the prover while it is registered
+		stake: BalanceOf<T>,
+		capabilities: ProverCapabilities<T>,
+		// Set once the prover unregisters: the block from which its stake can be withdrawn
+		unbonding_until: Option<T::BlockNumber>,
+	}
+
+	#[pallet::storage]
+	/// Registered provers, which may fulfill requests
+	pub(super) type Provers<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, ProverInfo<T>, OptionQuery>;
 
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
 		ProofRequested {
+			request_id: RequestId,
 			image_id: ImageId,
 			args: Vec<Vec<u32>>,
 		},
-		/// Proof was successfully verified and will be stored
-		ProofVerified,
+		/// Proof was successfully verified and stored. `journal_hash` is the SHA-256 digest of its
+		/// journal.
+		ProofVerified {
+			proof: ProofId,
+			image_id: ImageId,
+			request: Option<RequestId>,
+			prover: T::AccountId,
+			journal_hash: [u8; 32],
+		},
 		/// A program was uploaded
 		ProgramUploaded {
 			image_id: ImageId,
+			owner: T::AccountId,
+		},
+		/// A program was removed and its deposit returned to its owner
+		ProgramRemoved {
+			image_id: ImageId,
+		},
+		/// The ownership of a program, along with its deposit, was transferred
+		ProgramOwnershipTransferred {
+			image_id: ImageId,
+			from: Option<T::AccountId>,
+			to: T::AccountId,
+		},
+		/// A program was shown not to match its claimed image id. It was removed and the
+		/// deposit of its owner given to the challenger.
+		FraudulentProgramRemoved {
+			image_id: ImageId,
+			owner: Option<T::AccountId>,
+			challenger: T::AccountId,
+			slashed: BalanceOf<T>,
+		},
+		/// A request was fulfilled by a verified proof and its reward paid to the prover
+		RequestFulfilled {
+			request: RequestId,
+			prover: T::AccountId,
+			reward: BalanceOf<T>,
+		},
+		/// A request was cancelled by its requester and its reward returned
+		RequestCancelled {
+			request: RequestId,
+		},
+		/// A request reached its deadline without being fulfilled and its reward was returned
+		RequestExpired {
+			request: RequestId,
+		},
+		/// A prover registered, bonding `stake`
+		ProverRegistered {
+			prover: T::AccountId,
+			stake: BalanceOf<T>,
+		},
+		/// A prover unregistered. Its stake can be withdrawn from block `unbonding_until`.
+		ProverUnbonding {
+			prover: T::AccountId,
+			unbonding_until: T::BlockNumber,
+		},
+		/// An unregistered prover withdrew its stake
+		ProverWithdrawn {
+			prover: T::AccountId,
+			stake: BalanceOf<T>,
+		},
+		/// A prover claimed the exclusive right to fulfill a request until `expires_at`
+		RequestClaimed {
+			request: RequestId,
+			prover: T::AccountId,
+			expires_at: T::BlockNumber,
+		},
+		/// A claim lapsed without the request being fulfilled. The collateral of the prover and
+		/// `LapseSlash` of its stake were given to the requester, and the request reopened.
+		ClaimLapsed {
+			request: RequestId,
+			prover: T::AccountId,
+			slashed: BalanceOf<T>,
+			stake_slashed: BalanceOf<T>,
+		},
+		/// A request was put up for auction. Sealed bids may be committed until `bidding_end`,
+		/// and revealed until `reveal_end`.
+		AuctionOpened {
+			request: RequestId,
+			max_price: BalanceOf<T>,
+			bidding_end: T::BlockNumber,
+			reveal_end: T::BlockNumber,
+		},
+		/// A prover committed a sealed bid on an auctioned request
+		BidCommitted {
+			request: RequestId,
+			prover: T::AccountId,
+		},
+		/// A prover revealed its bid on an auctioned request
+		BidRevealed {
+			request: RequestId,
+			prover: T::AccountId,
+			price: BalanceOf<T>,
+		},
+		/// A bid was never revealed and its deposit was given to the requester
+		BidForfeited {
+			request: RequestId,
+			prover: T::AccountId,
+			slashed: BalanceOf<T>,
+		},
+		/// The auction of a request closed. The winner, if any, claims the request, and the
+		/// reward was lowered to `reward`.
+		AuctionClosed {
+			request: RequestId,
+			winner: Option<T::AccountId>,
+			reward: BalanceOf<T>,
+		},
+		/// The callback of a fulfilled request was dispatched
+		CallbackDispatched {
+			request: RequestId,
+		},
+		/// The callback of a fulfilled request failed. The request was settled nonetheless.
+		CallbackFailed {
+			request: RequestId,
+			error: DispatchError,
 		},
 	}
 
@@ -102,6 +680,142 @@ pub mod pallet {
 		ProofInvalid,
 		/// Proof did not pass verification
 		ProofNotVerified,
+		/// Tried to fulfill a request which does not exist
+		RequestDoesNotExist,
+		/// Tried to fulfill a request which was already fulfilled
+		RequestAlreadyFulfilled,
+		/// The request being fulfilled is for a different program
+		RequestImageMismatch,
+		/// The proof's journal does not commit to the args of the request being fulfilled
+		InputCommitmentMismatch,
+		/// The reward of the request being fulfilled is no longer fully reserved
+		RewardNotReserved,
+		/// The deadline of a request must be in the future
+		DeadlineInPast,
+		/// The deadline of a request is further away than `MaxRequestLifetime`
+		DeadlineTooFar,
+		/// Too many requests already expire at the requested deadline
+		TooManyExpiringRequests,
+		/// Only the requester may cancel a request
+		NotRequester,
+		/// The program is longer than `MaxProgramLength`
+		ProgramTooLong,
+		/// More than `MaxArgs` arguments were passed to a program
+		TooManyArgs,
+		/// An argument is longer than `MaxArgsLength`
+		ArgTooLong,
+		/// The proof has more than `MaxSegments` segment receipts
+		TooManySegments,
+		/// A segment seal is longer than `MaxProofLength`
+		SealTooLong,
+		/// Only the owner of a program may manage it
+		NotProgramOwner,
+		/// Tried to remove or transfer a program which still has open requests
+		ProgramHasOpenRequests,
+		/// The name of a program is longer than `MaxNameLength`
+		NameTooLong,
+		/// The description of a program is longer than `MaxDescriptionLength`
+		DescriptionTooLong,
+		/// The manifest of a program is longer than `MaxManifestLength`
+		ManifestTooLong,
+		/// The deposit of a program could not be moved to its new owner
+		DepositNotReserved,
+		/// The challenge period of the program is over
+		ChallengePeriodOver,
+		/// The program cannot be removed or transferred while its image id may still be challenged
+		ChallengePeriodNotOver,
+		/// The challenged program does match its claimed image id
+		ImageIdMatches,
+		/// The account is already registered as a prover
+		ProverAlreadyRegistered,
+		/// The account is not registered as a prover
+		NotProver,
+		/// Only registered provers which are not unbonding may fulfill requests
+		NotActiveProver,
+		/// The stake is lower than `MinProverStake`
+		StakeTooLow,
+		/// The risc0 version is longer than `MaxVersionLength`
+		VersionTooLong,
+		/// The prover is already unbonding
+		AlreadyUnbonding,
+		/// The prover has not unregistered, or its unbonding period is not over
+		StillBonded,
+		/// Another prover has an active claim on the request
+		RequestAlreadyClaimed,
+		/// Too many claims already lapse at the same block
+		TooManyExpiringClaims,
+		/// The request is being auctioned, and cannot be claimed, fulfilled or cancelled yet
+		AuctionOngoing,
+		/// The request is not being auctioned
+		NotAuctioned,
+		/// The deadline of an auctioned request must be after its auction closes
+		DeadlineBeforeAuctionEnd,
+		/// Too many auctions already close at the same block
+		TooManyClosingAuctions,
+		/// The bidding period of the auction is over
+		BiddingClosed,
+		/// Bids may only be revealed after the bidding period and before the auction closes
+		NotRevealPeriod,
+		/// The prover already committed a bid on the request
+		AlreadyBid,
+		/// `MaxBids` bids were already committed on the request
+		TooManyBids,
+		/// The prover has no unrevealed bid on the request
+		NoBid,
+		/// The revealed price and salt do not match the committed bid
+		BidMismatch,
+		/// The journal of a proof is longer than `MaxJournalLength`
+		JournalTooLong,
+		/// The encoded callback is longer than `MaxCallbackLength`
+		CallbackTooLong,
+		/// The weight of the callback is above `MaxCallbackWeight`
+		CallbackTooHeavy,
+		/// The callback can no longer be decoded, e.g. because the runtime was upgraded
+		CallbackUndecodable,
+		/// A proof of the program with the same journal is stored already, and the proof fulfills
+		/// no request
+		ProofAlreadyStored,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			// Hand auctioned requests over to their winners
+			let closing = AuctionCloses::<T>::take(now);
+			let closed_count = closing.len() as u32;
+			let mut forfeited_count = 0;
+			for request_id in closing {
+				forfeited_count += Self::close_auction(request_id);
+			}
+
+			// Reopen the requests whose claim lapsed, compensating their requesters
+			let lapsing = ClaimExpiries::<T>::take(now);
+			let lapsed_count = lapsing.len() as u32;
+			for request_id in lapsing {
+				Self::lapse_claim(request_id);
+			}
+
+			// Return the rewards of requests which reached their deadline unfulfilled
+			let expiring = RequestExpiries::<T>::take(now);
+			let expired_count = expiring.len() as u32;
+			for request_id in expiring {
+				// A claim which could not be scheduled to lapse earlier lapses with the request
+				Self::lapse_claim(request_id);
+				if let Some(proof_request) = ProofRequests::<T>::get(request_id) {
+					Self::close_request(request_id, &proof_request);
+					T::Currency::unreserve(&proof_request.requester, proof_request.reward);
+					Self::deposit_event(Event::RequestExpired { request: request_id });
+				}
+			}
+
+			// Drop the proofs which are no longer kept
+			let pruned_count = Self::prune_proofs(now);
+
+			T::WeightInfo::on_initialize_close_auctions(closed_count, forfeited_count)
+				.saturating_add(T::WeightInfo::on_initialize_lapse_claims(lapsed_count))
+				.saturating_add(T::WeightInfo::on_initialize_expire_requests(expired_count))
+				.saturating_add(T::WeightInfo::on_initialize_prune_proofs(pruned_count))
+		}
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -109,87 +823,1143 @@ pub mod pallet {
 	// Dispatchable functions must be annotated with a weight and must return a DispatchResult.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
-		/// An example dispatchable that takes a singles value as a parameter, writes the value to
-		/// storage and emits an event. This function must be dispatched by a signed extrinsic.
+		/// Upload a program along with some metadata describing it. The uploader becomes the
+		/// owner of the program, and a deposit is reserved for the space it takes up.
+		///
+		/// The optional `manifest` describes the inputs the program reads and the outputs it
+		/// commits to its journal, so that clients can encode args and decode journals without
+		/// reading the program's source.
 		#[pallet::call_index(0)]
-		#[pallet::weight(T::WeightInfo::do_something())]
+		#[pallet::weight(T::WeightInfo::upload_program(Pallet::<T>::max_program_bytes()))]
 		pub fn upload_program(
 			origin: OriginFor<T>,
-			// Todo: find how to verify image id
+			// Not checked on upload, but can be challenged during `ChallengePeriod`
 			image_id: ImageId,
 			// The bincode-serialized program
 			program: Vec<u8>,
-		) -> DispatchResult {
-			let _who = ensure_signed(origin)?;
+			name: Vec<u8>,
+			description: Vec<u8>,
+			source_hash: Option<[u8; 32]>,
+			manifest: Option<Vec<u8>>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
 			ensure!(!Programs::<T>::contains_key(image_id), Error::<T>::ProgramAlreadyExists);
+			let program: ProgramOf<T> =
+				program.try_into().map_err(|_| Error::<T>::ProgramTooLong)?;
+			let name: BoundedVec<_, _> = name.try_into().map_err(|_| Error::<T>::NameTooLong)?;
+			let description: BoundedVec<_, _> =
+				description.try_into().map_err(|_| Error::<T>::DescriptionTooLong)?;
+			let manifest: Option<BoundedVec<u8, T::MaxManifestLength>> = manifest
+				.map(|manifest| manifest.try_into().map_err(|_| Error::<T>::ManifestTooLong))
+				.transpose()?;
+
+			// The uploader pays for the state the program occupies until it is removed
+			let size = program.len() as u32;
+			let manifest_len = manifest.as_ref().map_or(0, |manifest| manifest.len() as u32);
+			let stored_bytes = size
+				.saturating_add(name.len() as u32 + description.len() as u32)
+				.saturating_add(manifest_len);
+			let deposit = T::DepositBase::get()
+				.saturating_add(T::DepositPerByte::get().saturating_mul(stored_bytes.into()));
+			T::Currency::reserve(&who, deposit)?;
 
+			let now = frame_system::Pallet::<T>::block_number();
 			<Programs<T>>::insert(image_id, program);
+			if let Some(manifest) = manifest {
+				ProgramManifests::<T>::insert(image_id, manifest);
+			}
+			ProgramInfos::<T>::insert(
+				image_id,
+				ProgramInfo {
+					owner: Some(who.clone()),
+					deposit,
+					uploaded_at: now,
+					size,
+					name,
+					description,
+					source_hash,
+					challenge_period_end: now.saturating_add(T::ChallengePeriod::get()),
+				},
+			);
 
-			Self::deposit_event(Event::ProgramUploaded { image_id });
-			Ok(())
+			Self::deposit_event(Event::ProgramUploaded { image_id, owner: who });
+			Ok(Some(T::WeightInfo::upload_program(stored_bytes)).into())
 		}
 
-		/// Request a proof of a known program, passing some arguments
+		/// Request a proof of a known program, passing some arguments. The reward is reserved
+		/// until the request is fulfilled, cancelled, or expires at `deadline`.
+		///
+		/// The optional `callback` is dispatched with the `Request` origin once the request is
+		/// fulfilled, and can read the verified journal with `request_journal`. The fee for its
+		/// weight is reserved from the requester, and paid to the prover which fulfills the
+		/// request.
 		#[pallet::call_index(1)]
-		#[pallet::weight(T::WeightInfo::cause_error())]
+		#[pallet::weight(T::WeightInfo::request_proof())]
 		pub fn request_proof(
 			origin: OriginFor<T>,
 			image_id: ImageId,
 			args: Vec<Vec<u32>>,
 			reward: BalanceOf<T>,
+			deadline: T::BlockNumber,
+			callback: Option<Box<<T as Config>::RuntimeCall>>,
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
+			Self::open_request(who, image_id, args, reward, deadline, callback)?;
+			Ok(())
+		}
+
+		/// An extrinsic which verifies proofs for programs, forming a trustless relationship for
+		/// others to check the verification result. `request_id` names the request being
+		/// fulfilled, if any. A proof which fulfills no request is rejected if a proof of the
+		/// program with the same journal is stored already. The verification of `MaxSegments`
+		/// segments is charged up front, and refunded down to the number of segments submitted
+		/// once the proof is verified.
+		#[pallet::call_index(2)]
+		#[pallet::weight(
+			T::WeightInfo::store_and_verify_proof()
+				.saturating_add(T::WeightInfo::verify_receipt(T::MaxSegments::get()))
+				.saturating_add(T::OnProofVerified::weight())
+				.saturating_add(T::MaxCallbackWeight::get())
+		)]
+		pub fn store_and_verify_proof(
+			origin: OriginFor<T>,
+			image_id: ImageId,
+			request_id: Option<RequestId>,
+			receipt_data: Vec<(Vec<u32>, u32)>,
+			journal: Vec<u8>,
+		) -> DispatchResultWithPostInfo {
+			let who = ensure_signed(origin)?;
+			ensure!(Programs::<T>::contains_key(image_id), Error::<T>::ProgramDoesNotExist);
+			let bounded_receipt = Self::bound_receipt(receipt_data.clone())?;
+			let segments = bounded_receipt.len() as u32;
+			let bounded_journal: JournalOf<T> =
+				journal.clone().try_into().map_err(|_| Error::<T>::JournalTooLong)?;
 
-			T::Currency::reserve(&who, reward)?;
+			// Check the request being fulfilled up front, but only settle it once the receipt
+			// has been verified. Only registered provers are rewarded for fulfilling requests.
+			let proof_request = request_id
+				.map(|request_id| -> Result<_, DispatchError> {
+					ensure!(Self::is_active_prover(&who), Error::<T>::NotActiveProver);
+					let proof_request = ProofRequests::<T>::get(request_id).ok_or_else(|| {
+						if FulfilledRequests::<T>::contains_key(request_id) {
+							Error::<T>::RequestAlreadyFulfilled
+						} else {
+							Error::<T>::RequestDoesNotExist
+						}
+					})?;
+					ensure!(proof_request.image_id == image_id, Error::<T>::RequestImageMismatch);
+					ensure!(!Auctions::<T>::contains_key(request_id), Error::<T>::AuctionOngoing);
+					ensure!(
+						proof_request.claim.as_ref().map_or(true, |claim| claim.prover == who),
+						Error::<T>::RequestAlreadyClaimed
+					);
+					ensure!(
+						journal.starts_with(&proof_request.input_commitment),
+						Error::<T>::InputCommitmentMismatch
+					);
+					Ok((request_id, proof_request))
+				})
+				.transpose()?;
 
-			ProofRequests::<T>::insert(
+			// A proof which fulfills no request only adds to storage if its journal is new
+			let journal_hash = sha2_256(&bounded_journal);
+			ensure!(
+				request_id.is_some() || !ProofByJournal::<T>::contains_key(image_id, journal_hash),
+				Error::<T>::ProofAlreadyStored
+			);
+
+			Self::verify_receipt(image_id, receipt_data, journal)?;
+
+			let proof_id = Self::store_proof(StoredProof {
 				image_id,
-				ProofRequest { requester: who, reward, args: args.clone() },
+				receipt: bounded_receipt,
+				journal: bounded_journal.clone(),
+				prover: who.clone(),
+				request: request_id,
+				verified_at: frame_system::Pallet::<T>::block_number(),
+			})?;
+
+			Self::deposit_event(Event::<T>::ProofVerified {
+				proof: proof_id,
+				image_id,
+				request: request_id,
+				prover: who.clone(),
+				journal_hash,
+			});
+
+			// The submitter of a proof fulfilling a request receives the designated reward. The
+			// callback is taken beforehand, so that closing the request does not refund its fee.
+			let mut callback_weight = Weight::zero();
+			if let Some((request_id, proof_request)) = proof_request {
+				let callback = Callbacks::<T>::take(request_id);
+				let requester = proof_request.requester.clone();
+				Self::settle_request(request_id, proof_request, who.clone())?;
+				if let Some(callback) = callback {
+					callback_weight =
+						Self::dispatch_callback(request_id, callback, &requester, &who);
+				}
+			}
+
+			T::OnProofVerified::on_proof_verified(image_id, request_id, &bounded_journal, &who);
+
+			// Refund the verification of the segments which were not submitted, and the unused
+			// part of the weight reserved for callbacks
+			Ok(Some(
+				T::WeightInfo::store_and_verify_proof()
+					.saturating_add(T::WeightInfo::verify_receipt(segments))
+					.saturating_add(T::OnProofVerified::weight())
+					.saturating_add(callback_weight),
+			)
+			.into())
+		}
+
+		/// Cancel an open request, returning its reward to the requester
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::cancel_request())]
+		pub fn cancel_request(origin: OriginFor<T>, request_id: RequestId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let proof_request =
+				ProofRequests::<T>::get(request_id).ok_or(Error::<T>::RequestDoesNotExist)?;
+			ensure!(proof_request.requester == who, Error::<T>::NotRequester);
+			// Bidders have deposits at stake until the auction closes
+			ensure!(!Auctions::<T>::contains_key(request_id), Error::<T>::AuctionOngoing);
+			// The claimant is already working on the request, and has collateral at stake
+			ensure!(proof_request.claim.is_none(), Error::<T>::RequestAlreadyClaimed);
+
+			Self::close_request(request_id, &proof_request);
+			T::Currency::unreserve(&who, proof_request.reward);
+
+			Self::deposit_event(Event::RequestCancelled { request: request_id });
+			Ok(())
+		}
+
+		/// Remove a program which has no open requests and whose challenge period is over,
+		/// returning its deposit to its owner. Root may remove any program, including those which
+		/// have no owner.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::remove_program())]
+		pub fn remove_program(origin: OriginFor<T>, image_id: ImageId) -> DispatchResult {
+			let maybe_who = ensure_signed_or_root(origin)?;
+			let info = ProgramInfos::<T>::get(image_id).ok_or(Error::<T>::ProgramDoesNotExist)?;
+			Self::ensure_program_owner(&maybe_who, &info)?;
+			ensure!(!Self::has_open_requests(image_id), Error::<T>::ProgramHasOpenRequests);
+			// Otherwise the owner of a fraudulent program could remove it ahead of a challenge,
+			// and keep its deposit
+			ensure!(
+				frame_system::Pallet::<T>::block_number() >= info.challenge_period_end,
+				Error::<T>::ChallengePeriodNotOver
 			);
 
-			Self::deposit_event(Event::ProofRequested { image_id, args });
+			Programs::<T>::remove(image_id);
+			ProgramInfos::<T>::remove(image_id);
+			ProgramManifests::<T>::remove(image_id);
+			if let Some(owner) = &info.owner {
+				T::Currency::unreserve(owner, info.deposit);
+			}
 
+			Self::deposit_event(Event::ProgramRemoved { image_id });
 			Ok(())
 		}
 
-		/// An extrinsic which verifies proofs for programs, forming a trustless relationship for
-		/// others to check the verification result
-		#[pallet::call_index(2)]
-		#[pallet::weight(T::WeightInfo::cause_error())]
-		pub fn store_and_verify_proof(
+		/// Transfer the ownership of a program which has no open requests to another account.
+		/// The deposit of the program is moved to the new owner. Root may transfer any program,
+		/// including those which have no owner.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::transfer_program_ownership())]
+		pub fn transfer_program_ownership(
 			origin: OriginFor<T>,
 			image_id: ImageId,
-			receipt_data: Vec<(Vec<u32>, u32)>,
-			journal: Vec<u8>,
+			new_owner: AccountIdLookupOf<T>,
+		) -> DispatchResult {
+			let maybe_who = ensure_signed_or_root(origin)?;
+			let new_owner = T::Lookup::lookup(new_owner)?;
+			let from = ProgramInfos::<T>::try_mutate(
+				image_id,
+				|maybe_info| -> Result<_, DispatchError> {
+					let info = maybe_info.as_mut().ok_or(Error::<T>::ProgramDoesNotExist)?;
+					Self::ensure_program_owner(&maybe_who, info)?;
+					ensure!(!Self::has_open_requests(image_id), Error::<T>::ProgramHasOpenRequests);
+					ensure!(
+						frame_system::Pallet::<T>::block_number() >= info.challenge_period_end,
+						Error::<T>::ChallengePeriodNotOver
+					);
+
+					// Programs without an owner were stored without a deposit
+					if let Some(owner) = &info.owner {
+						let unmoved = T::Currency::repatriate_reserved(
+							owner,
+							&new_owner,
+							info.deposit,
+							BalanceStatus::Reserved,
+						)?;
+						ensure!(unmoved.is_zero(), Error::<T>::DepositNotReserved);
+					}
+					Ok(info.owner.replace(new_owner.clone()))
+				},
+			)?;
+
+			Self::deposit_event(Event::ProgramOwnershipTransferred {
+				image_id,
+				from,
+				to: new_owner,
+			});
+			Ok(())
+		}
+
+		/// Prove that a program does not match the image id it was uploaded under, during its
+		/// challenge period. The image id is computed from the stored ELF, and if it differs, the
+		/// program is removed and the deposit of its owner is given to the challenger. Open
+		/// requests for the program are left to expire. Failed challenges pay for the worst case
+		/// weight.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::challenge_image_id(T::MaxProgramLength::get()))]
+		pub fn challenge_image_id(
+			origin: OriginFor<T>,
+			image_id: ImageId,
+		) -> DispatchResultWithPostInfo {
+			let challenger = ensure_signed(origin)?;
+			let info = ProgramInfos::<T>::get(image_id).ok_or(Error::<T>::ProgramDoesNotExist)?;
+			ensure!(
+				frame_system::Pallet::<T>::block_number() < info.challenge_period_end,
+				Error::<T>::ChallengePeriodOver
+			);
+			let program = Programs::<T>::get(image_id).ok_or(Error::<T>::ProgramDoesNotExist)?;
+
+			// A program which is not a valid ELF cannot match any image id
+			let computed_image_id = program_image::compute_image_id(&program);
+			ensure!(computed_image_id != Some(image_id), Error::<T>::ImageIdMatches);
+
+			Programs::<T>::remove(image_id);
+			ProgramInfos::<T>::remove(image_id);
+			ProgramManifests::<T>::remove(image_id);
+			let unslashed = match &info.owner {
+				Some(owner) => T::Currency::repatriate_reserved(
+					owner,
+					&challenger,
+					info.deposit,
+					BalanceStatus::Free,
+				)?,
+				None => info.deposit,
+			};
+
+			Self::deposit_event(Event::FraudulentProgramRemoved {
+				image_id,
+				owner: info.owner,
+				challenger,
+				slashed: info.deposit.saturating_sub(unslashed),
+			});
+			Ok(Some(T::WeightInfo::challenge_image_id(info.size)).into())
+		}
+
+		/// Register as a prover, bonding `stake` and declaring what the prover is able to prove
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::register_prover())]
+		pub fn register_prover(
+			origin: OriginFor<T>,
+			stake: BalanceOf<T>,
+			max_cycles: u64,
+			risc0_version: Vec<u8>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!Provers::<T>::contains_key(&who), Error::<T>::ProverAlreadyRegistered);
+			ensure!(stake >= T::MinProverStake::get(), Error::<T>::StakeTooLow);
+			let risc0_version = risc0_version.try_into().map_err(|_| Error::<T>::VersionTooLong)?;
+
+			T::Currency::reserve(&who, stake)?;
+			Provers::<T>::insert(
+				&who,
+				ProverInfo {
+					stake,
+					capabilities: ProverCapabilities { max_cycles, risc0_version },
+					unbonding_until: None,
+				},
+			);
+
+			Self::deposit_event(Event::ProverRegistered { prover: who, stake });
+			Ok(())
+		}
+
+		/// Stop proving. The stake of the prover stays bonded for `UnbondingPeriod` blocks, after
+		/// which it can be withdrawn with `withdraw_unbonded`.
+		#[pallet::call_index(8)]
+		#[pallet::weight(T::WeightInfo::unregister_prover())]
+		pub fn unregister_prover(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let unbonding_until = Provers::<T>::try_mutate(&who, |maybe_info| {
+				let info = maybe_info.as_mut().ok_or(Error::<T>::NotProver)?;
+				ensure!(info.unbonding_until.is_none(), Error::<T>::AlreadyUnbonding);
+				let unbonding_until = frame_system::Pallet::<T>::block_number()
+					.saturating_add(T::UnbondingPeriod::get());
+				info.unbonding_until = Some(unbonding_until);
+				Ok::<_, Error<T>>(unbonding_until)
+			})?;
+
+			Self::deposit_event(Event::ProverUnbonding { prover: who, unbonding_until });
+			Ok(())
+		}
+
+		/// Withdraw the stake of an unregistered prover once its unbonding period is over
+		#[pallet::call_index(9)]
+		#[pallet::weight(T::WeightInfo::withdraw_unbonded())]
+		pub fn withdraw_unbonded(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let info = Provers::<T>::get(&who).ok_or(Error::<T>::NotProver)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(
+				info.unbonding_until.map_or(false, |until| now >= until),
+				Error::<T>::StillBonded
+			);
+
+			Provers::<T>::remove(&who);
+			T::Currency::unreserve(&who, info.stake);
+
+			Self::deposit_event(Event::ProverWithdrawn { prover: who, stake: info.stake });
+			Ok(())
+		}
+
+		/// Claim the exclusive right to fulfill a request for `ClaimWindow` blocks, or until the
+		/// request's deadline if that is sooner. `ClaimCollateral` is reserved from the prover
+		/// and returned once it fulfills the request, or given to the requester if the claim
+		/// lapses, after which the request reopens for other provers.
+		#[pallet::call_index(10)]
+		#[pallet::weight(T::WeightInfo::claim_request())]
+		pub fn claim_request(origin: OriginFor<T>, request_id: RequestId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Self::is_active_prover(&who), Error::<T>::NotActiveProver);
+			ensure!(!Auctions::<T>::contains_key(request_id), Error::<T>::AuctionOngoing);
+
+			let expires_at = ProofRequests::<T>::try_mutate(request_id, |maybe_request| {
+				let proof_request =
+					maybe_request.as_mut().ok_or(Error::<T>::RequestDoesNotExist)?;
+				ensure!(proof_request.claim.is_none(), Error::<T>::RequestAlreadyClaimed);
+
+				let expires_at = frame_system::Pallet::<T>::block_number()
+					.saturating_add(T::ClaimWindow::get())
+					.min(proof_request.deadline);
+				let collateral = T::ClaimCollateral::get();
+				T::Currency::reserve(&who, collateral)?;
+				ClaimExpiries::<T>::try_append(expires_at, request_id)
+					.map_err(|_| Error::<T>::TooManyExpiringClaims)?;
+
+				proof_request.claim = Some(Claim { prover: who.clone(), expires_at, collateral });
+				Ok::<_, DispatchError>(expires_at)
+			})?;
+
+			Self::deposit_event(Event::RequestClaimed {
+				request: request_id,
+				prover: who,
+				expires_at,
+			});
+			Ok(())
+		}
+
+		/// Request a proof like `request_proof`, but let registered provers bid for it. Sealed bids
+		/// are committed during `BiddingPeriod` and revealed during the following `RevealPeriod`.
+		/// `max_price` is reserved up front, and once the auction closes the lowest valid bid
+		/// claims the request at its price, the difference being returned to the requester. If
+		/// there is no valid bid, the request stays open at `max_price`.
+		#[pallet::call_index(11)]
+		#[pallet::weight(T::WeightInfo::request_proof_auction())]
+		pub fn request_proof_auction(
+			origin: OriginFor<T>,
+			image_id: ImageId,
+			args: Vec<Vec<u32>>,
+			max_price: BalanceOf<T>,
+			deadline: T::BlockNumber,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let now = frame_system::Pallet::<T>::block_number();
+			let bidding_end = now.saturating_add(T::BiddingPeriod::get());
+			let reveal_end = bidding_end.saturating_add(T::RevealPeriod::get());
+			ensure!(deadline > reveal_end, Error::<T>::DeadlineBeforeAuctionEnd);
+
+			let request_id = Self::open_request(who, image_id, args, max_price, deadline, None)?;
+			Auctions::<T>::insert(
+				request_id,
+				Auction { bidding_end, reveal_end, bids: 0, lowest_bid: None },
+			);
+			AuctionCloses::<T>::try_append(reveal_end, request_id)
+				.map_err(|_| Error::<T>::TooManyClosingAuctions)?;
+
+			Self::deposit_event(Event::AuctionOpened {
+				request: request_id,
+				max_price,
+				bidding_end,
+				reveal_end,
+			});
+			Ok(())
+		}
+
+		/// Commit a sealed bid on an auctioned request, reserving `BidDeposit`. The commitment is
+		/// computed with `bid_commitment`, and must be revealed with `reveal_bid` once bidding
+		/// is over.
+		#[pallet::call_index(12)]
+		#[pallet::weight(T::WeightInfo::commit_bid())]
+		pub fn commit_bid(
+			origin: OriginFor<T>,
+			request_id: RequestId,
+			commitment: [u8; 32],
 		) -> DispatchResult {
 			let who = ensure_signed(origin)?;
-			ensure!(Programs::<T>::get(image_id).is_some(), Error::<T>::ProgramDoesNotExist);
+			ensure!(Self::is_active_prover(&who), Error::<T>::NotActiveProver);
+			ensure!(!Bids::<T>::contains_key(request_id, &who), Error::<T>::AlreadyBid);
+
+			Auctions::<T>::try_mutate(request_id, |maybe_auction| -> DispatchResult {
+				let auction = maybe_auction.as_mut().ok_or(Error::<T>::NotAuctioned)?;
+				ensure!(
+					frame_system::Pallet::<T>::block_number() < auction.bidding_end,
+					Error::<T>::BiddingClosed
+				);
+				ensure!(auction.bids < T::MaxBids::get(), Error::<T>::TooManyBids);
+
+				let deposit = T::BidDeposit::get();
+				T::Currency::reserve(&who, deposit)?;
+				Bids::<T>::insert(request_id, &who, SealedBid { commitment, deposit });
+				auction.bids += 1;
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::BidCommitted { request: request_id, prover: who });
+			Ok(())
+		}
+
+		/// Reveal a sealed bid once the bidding period is over. Bids above the maximum price of
+		/// the request are invalid. The deposit of a bid which is not the lowest is returned right
+		/// away; ties go to the bid revealed first.
+		#[pallet::call_index(13)]
+		#[pallet::weight(T::WeightInfo::reveal_bid())]
+		pub fn reveal_bid(
+			origin: OriginFor<T>,
+			request_id: RequestId,
+			price: BalanceOf<T>,
+			salt: [u8; 32],
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let max_price = ProofRequests::<T>::get(request_id)
+				.ok_or(Error::<T>::RequestDoesNotExist)?
+				.reward;
+
+			Auctions::<T>::try_mutate(request_id, |maybe_auction| -> DispatchResult {
+				let auction = maybe_auction.as_mut().ok_or(Error::<T>::NotAuctioned)?;
+				let now = frame_system::Pallet::<T>::block_number();
+				ensure!(
+					now >= auction.bidding_end && now < auction.reveal_end,
+					Error::<T>::NotRevealPeriod
+				);
+				let bid = Bids::<T>::get(request_id, &who).ok_or(Error::<T>::NoBid)?;
+				ensure!(
+					bid.commitment == Self::bid_commitment(&who, price, &salt),
+					Error::<T>::BidMismatch
+				);
+				Bids::<T>::remove(request_id, &who);
+
+				let is_lowest = price <= max_price &&
+					auction.lowest_bid.as_ref().map_or(true, |lowest| price < lowest.price);
+				if is_lowest {
+					let outbid = auction.lowest_bid.replace(RevealedBid {
+						prover: who.clone(),
+						price,
+						deposit: bid.deposit,
+					});
+					if let Some(outbid) = outbid {
+						T::Currency::unreserve(&outbid.prover, outbid.deposit);
+					}
+				} else {
+					T::Currency::unreserve(&who, bid.deposit);
+				}
+				Ok(())
+			})?;
+
+			Self::deposit_event(Event::BidRevealed { request: request_id, prover: who, price });
+			Ok(())
+		}
+	}
+
+	impl<T: Config> Pallet<T> {
+		/// The commitment to a request's args which a guest must commit as the first 32 bytes of
+		/// its journal: the SHA-256 digest of the little-endian bytes of every input word, in the
+		/// order the guest reads them.
+		pub fn input_commitment<A: AsRef<[u32]>>(args: &[A]) -> [u8; 32] {
+			let bytes: Vec<u8> = args
+				.iter()
+				.flat_map(|arg| arg.as_ref().iter().flat_map(|word| word.to_le_bytes()))
+				.collect();
+			sha2_256(&bytes)
+		}
+
+		/// The commitment to a sealed bid of `price` by `bidder`, blinded by a secret `salt`: the
+		/// BLAKE2-256 digest of the SCALE-encoded `(bidder, price, salt)`.
+		pub fn bid_commitment(
+			bidder: &T::AccountId,
+			price: BalanceOf<T>,
+			salt: &[u8; 32],
+		) -> [u8; 32] {
+			(bidder, price, salt).using_encoded(blake2_256)
+		}
+
+		/// The most bytes an uploaded program can store: the program, its name, description and
+		/// manifest
+		fn max_program_bytes() -> u32 {
+			T::MaxProgramLength::get()
+				.saturating_add(T::MaxNameLength::get())
+				.saturating_add(T::MaxDescriptionLength::get())
+				.saturating_add(T::MaxManifestLength::get())
+		}
+
+		/// Check that a program may be managed by the signer of a call, or by root if `maybe_who`
+		/// is `None`
+		fn ensure_program_owner(
+			maybe_who: &Option<T::AccountId>,
+			info: &ProgramInfo<T>,
+		) -> DispatchResult {
+			ensure!(maybe_who.is_none() || *maybe_who == info.owner, Error::<T>::NotProgramOwner);
+			Ok(())
+		}
+
+		/// Check the arguments of a request against `MaxArgs` and `MaxArgsLength`
+		fn bound_args(args: Vec<Vec<u32>>) -> Result<ArgsOf<T>, Error<T>> {
+			ensure!(args.len() <= T::MaxArgs::get() as usize, Error::<T>::TooManyArgs);
+			args.into_iter()
+				.map(|arg| ArgOf::<T>::try_from(arg).map_err(|_| Error::<T>::ArgTooLong))
+				.collect::<Result<Vec<_>, _>>()?
+				.try_into()
+				.map_err(|_| Error::<T>::TooManyArgs)
+		}
+
+		/// Check the segment receipts of a proof against `MaxSegments` and `MaxProofLength`
+		pub(crate) fn bound_receipt(
+			receipt_data: Vec<(Vec<u32>, u32)>,
+		) -> Result<ReceiptOf<T>, Error<T>> {
+			ensure!(
+				receipt_data.len() <= T::MaxSegments::get() as usize,
+				Error::<T>::TooManySegments
+			);
+			receipt_data
+				.into_iter()
+				.map(|(seal, index)| {
+					SealOf::<T>::try_from(seal)
+						.map(|seal| (seal, index))
+						.map_err(|_| Error::<T>::SealTooLong)
+				})
+				.collect::<Result<Vec<_>, _>>()?
+				.try_into()
+				.map_err(|_| Error::<T>::TooManySegments)
+		}
+
+		/// Open a request for a proof, reserving its reward from the requester
+		fn open_request(
+			requester: T::AccountId,
+			image_id: ImageId,
+			args: Vec<Vec<u32>>,
+			reward: BalanceOf<T>,
+			deadline: T::BlockNumber,
+			callback: Option<Box<<T as Config>::RuntimeCall>>,
+		) -> Result<RequestId, DispatchError> {
+			let bounded_args = Self::bound_args(args.clone())?;
+
+			let now = frame_system::Pallet::<T>::block_number();
+			ensure!(deadline > now, Error::<T>::DeadlineInPast);
+			ensure!(
+				deadline <= now.saturating_add(T::MaxRequestLifetime::get()),
+				Error::<T>::DeadlineTooFar
+			);
+
+			let request_id = NextRequestId::<T>::try_mutate(|next| -> Result<_, DispatchError> {
+				let request_id = *next;
+				*next = next.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+				Ok(request_id)
+			})?;
+
+			T::Currency::reserve(&requester, reward)?;
+
+			if let Some(call) = callback {
+				let weight = call.get_dispatch_info().weight;
+				ensure!(weight.all_lte(T::MaxCallbackWeight::get()), Error::<T>::CallbackTooHeavy);
+				let call = call.encode().try_into().map_err(|_| Error::<T>::CallbackTooLong)?;
+				let fee = T::WeightToFee::weight_to_fee(&weight);
+				T::Currency::reserve(&requester, fee)?;
+				Callbacks::<T>::insert(request_id, Callback { call, weight, fee });
+			}
+
+			let input_commitment = Self::input_commitment(&args);
+			ProofRequests::<T>::insert(
+				request_id,
+				ProofRequest {
+					image_id,
+					requester,
+					reward,
+					args: bounded_args,
+					input_commitment,
+					deadline,
+					claim: None,
+				},
+			);
+			RequestsByImage::<T>::insert(image_id, request_id, ());
+			RequestExpiries::<T>::try_append(deadline, request_id)
+				.map_err(|_| Error::<T>::TooManyExpiringRequests)?;
+
+			Self::deposit_event(Event::ProofRequested { request_id, image_id, args });
+			Ok(request_id)
+		}
+
+		/// The id the next request will be assigned
+		pub fn next_request_id() -> RequestId {
+			NextRequestId::<T>::get()
+		}
+
+		/// Open requests with an id of at least `from`, in order of their ids, optionally only
+		/// those for `image_id`. At most `limit` requests are returned. The request ids are walked
+		/// in order from `from`, so the cost grows with the number of requests made since `from`,
+		/// whether they are still open or not.
+		pub fn open_requests(
+			image_id: Option<ImageId>,
+			from: RequestId,
+			limit: u32,
+		) -> Vec<RequestDetails<T::AccountId, BalanceOf<T>, T::BlockNumber>> {
+			(from..NextRequestId::<T>::get())
+				.filter_map(|request_id| Some((request_id, ProofRequests::<T>::get(request_id)?)))
+				.filter(|(_, proof_request)| {
+					image_id.map_or(true, |id| proof_request.image_id == id)
+				})
+				.take(limit as usize)
+				.map(|(request_id, proof_request)| RequestDetails {
+					request_id,
+					image_id: proof_request.image_id,
+					requester: proof_request.requester,
+					reward: proof_request.reward,
+					args: proof_request.args.into_iter().map(Into::into).collect(),
+					input_commitment: proof_request.input_commitment,
+					deadline: proof_request.deadline,
+					claim: proof_request.claim.map(|claim| (claim.prover, claim.expires_at)),
+					auctioned: Auctions::<T>::contains_key(request_id),
+				})
+				.collect()
+		}
+
+		/// Owner and metadata of an uploaded program
+		pub fn program_details(
+			image_id: ImageId,
+		) -> Option<ProgramDetails<T::AccountId, BalanceOf<T>, T::BlockNumber>> {
+			let info = ProgramInfos::<T>::get(image_id)?;
+			Some(ProgramDetails {
+				image_id,
+				owner: info.owner,
+				deposit: info.deposit,
+				uploaded_at: info.uploaded_at,
+				size: info.size,
+				name: info.name.into(),
+				description: info.description.into(),
+				source_hash: info.source_hash,
+				challenge_period_end: info.challenge_period_end,
+				latest_proof: LatestProofByImage::<T>::get(image_id),
+				manifest: Self::program_manifest(image_id),
+			})
+		}
+
+		/// The manifest a program was uploaded with, if any
+		pub fn program_manifest(image_id: ImageId) -> Option<Vec<u8>> {
+			ProgramManifests::<T>::get(image_id).map(Into::into)
+		}
+
+		/// The bincode-serialized ELF of an uploaded program
+		pub fn program(image_id: ImageId) -> Option<Vec<u8>> {
+			Programs::<T>::get(image_id).map(Into::into)
+		}
+
+		/// A stored proof, along with its journal
+		pub fn proof_details(
+			proof_id: ProofId,
+		) -> Option<ProofDetails<T::AccountId, T::BlockNumber>> {
+			let proof = Proofs::<T>::get(proof_id)?;
+			Some(ProofDetails {
+				proof_id,
+				image_id: proof.image_id,
+				receipt: proof
+					.receipt
+					.into_iter()
+					.map(|(seal, index)| (seal.into(), index))
+					.collect(),
+				journal: proof.journal.into(),
+				prover: proof.prover,
+				request: proof.request,
+				verified_at: proof.verified_at,
+			})
+		}
+
+		/// The id of the stored proof which fulfilled a request
+		pub fn request_proof_id(request_id: RequestId) -> Option<ProofId> {
+			ProofByRequest::<T>::get(request_id)
+		}
+
+		/// The journal of the most recent stored proof of a program
+		pub fn verified_journal(image_id: ImageId) -> Option<JournalOf<T>> {
+			LatestProofByImage::<T>::get(image_id).and_then(Self::proof_journal)
+		}
+
+		/// The journal of a stored proof
+		pub fn proof_journal(proof_id: ProofId) -> Option<JournalOf<T>> {
+			Proofs::<T>::get(proof_id).map(|proof| proof.journal)
+		}
+
+		/// The journal of the proof which fulfilled a request, while it is stored
+		pub fn request_journal(request_id: RequestId) -> Option<JournalOf<T>> {
+			ProofByRequest::<T>::get(request_id).and_then(Self::proof_journal)
+		}
+
+		/// Store a verified proof under a new proof id, and index it
+		pub(crate) fn store_proof(proof: StoredProof<T>) -> Result<ProofId, DispatchError> {
+			let proof_id = NextProofId::<T>::try_mutate(|next| -> Result<_, DispatchError> {
+				let proof_id = *next;
+				*next = next.checked_add(1).ok_or(ArithmeticError::Overflow)?;
+				Ok(proof_id)
+			})?;
+
+			ProofsByImage::<T>::insert(proof.image_id, proof_id, ());
+			ProofsByProver::<T>::insert(&proof.prover, proof_id, ());
+			LatestProofByImage::<T>::insert(proof.image_id, proof_id);
+			ProofByJournal::<T>::insert(proof.image_id, sha2_256(&proof.journal), proof_id);
+			if let Some(request_id) = proof.request {
+				ProofByRequest::<T>::insert(request_id, proof_id);
+			}
+			Proofs::<T>::insert(proof_id, proof);
+			Ok(proof_id)
+		}
+
+		/// Remove a stored proof, its index entries, and the record of the request it fulfilled
+		fn remove_proof(proof_id: ProofId) {
+			if let Some(proof) = Proofs::<T>::take(proof_id) {
+				ProofsByImage::<T>::remove(proof.image_id, proof_id);
+				ProofsByProver::<T>::remove(&proof.prover, proof_id);
+				LatestProofByImage::<T>::mutate_exists(proof.image_id, |latest| {
+					if *latest == Some(proof_id) {
+						*latest = None;
+					}
+				});
+				ProofByJournal::<T>::mutate_exists(
+					proof.image_id,
+					sha2_256(&proof.journal),
+					|latest| {
+						if *latest == Some(proof_id) {
+							*latest = None;
+						}
+					},
+				);
+				if let Some(request_id) = proof.request {
+					ProofByRequest::<T>::remove(request_id);
+					FulfilledRequests::<T>::remove(request_id);
+				}
+			}
+		}
+
+		/// Prune the oldest proofs which `ProofPruning` no longer keeps, up to
+		/// `MaxProofsPrunedPerBlock` of them. Returns the number of pruned proofs.
+		fn prune_proofs(now: T::BlockNumber) -> u32 {
+			let policy = T::ProofPruning::get();
+			if policy == PruningPolicy::KeepAll {
+				return 0
+			}
+
+			// Proof ids are assigned in order of verification, so the oldest proofs are pruned
+			// first
+			let next_id = NextProofId::<T>::get();
+			let mut oldest_id = OldestProofId::<T>::get();
+			let mut pruned = 0;
+			while pruned < T::MaxProofsPrunedPerBlock::get() && oldest_id < next_id {
+				let expired = match policy {
+					PruningPolicy::KeepAll => false,
+					PruningPolicy::KeepLastOverall(count) => next_id - oldest_id > count as ProofId,
+					PruningPolicy::KeepFor(period) => Proofs::<T>::get(oldest_id)
+						.map_or(true, |proof| proof.verified_at.saturating_add(period) <= now),
+				};
+				if !expired {
+					break
+				}
+				Self::remove_proof(oldest_id);
+				oldest_id += 1;
+				pruned += 1;
+			}
+
+			if pruned > 0 {
+				OldestProofId::<T>::put(oldest_id);
+			}
+			pruned
+		}
+
+		/// Whether the account is a registered prover which is not unbonding, and whose stake has
+		/// not been slashed below `MinProverStake`
+		pub fn is_active_prover(who: &T::AccountId) -> bool {
+			Provers::<T>::get(who).map_or(false, |info| {
+				info.unbonding_until.is_none() && info.stake >= T::MinProverStake::get()
+			})
+		}
+
+		/// The stake and capabilities of a registered prover
+		pub fn prover_details(
+			who: &T::AccountId,
+		) -> Option<ProverDetails<BalanceOf<T>, T::BlockNumber>> {
+			let info = Provers::<T>::get(who)?;
+			Some(ProverDetails {
+				active: Self::is_active_prover(who),
+				stake: info.stake,
+				max_cycles: info.capabilities.max_cycles,
+				risc0_version: info.capabilities.risc0_version.into(),
+				unbonding_until: info.unbonding_until,
+			})
+		}
+
+		/// Whether any request for the program is still open
+		fn has_open_requests(image_id: ImageId) -> bool {
+			RequestsByImage::<T>::iter_prefix(image_id).next().is_some()
+		}
+
+		/// Remove an open request from storage and from the indexes referencing it
+		fn close_request(request_id: RequestId, proof_request: &ProofRequest<T>) {
+			ProofRequests::<T>::remove(request_id);
+			RequestsByImage::<T>::remove(proof_request.image_id, request_id);
+			RequestExpiries::<T>::mutate_exists(proof_request.deadline, |maybe_expiring| {
+				Self::unschedule(maybe_expiring, request_id)
+			});
+			if let Some(claim) = &proof_request.claim {
+				ClaimExpiries::<T>::mutate_exists(claim.expires_at, |maybe_expiring| {
+					Self::unschedule(maybe_expiring, request_id)
+				});
+			}
+			if let Some(callback) = Callbacks::<T>::take(request_id) {
+				T::Currency::unreserve(&proof_request.requester, callback.fee);
+			}
+		}
+
+		/// Dispatch the callback of a fulfilled request with the `Request` origin, paying its
+		/// prepaid fee to the prover. A failing callback is reverted and reported in an event,
+		/// without reverting the settlement of the request. Returns the weight used.
+		fn dispatch_callback(
+			request_id: RequestId,
+			callback: Callback<T>,
+			requester: &T::AccountId,
+			prover: &T::AccountId,
+		) -> Weight {
+			// The prover paid for the weight of the callback as part of its transaction fee
+			let _ = T::Currency::repatriate_reserved(
+				requester,
+				prover,
+				callback.fee,
+				BalanceStatus::Free,
+			);
+
+			let call = match <T as Config>::RuntimeCall::decode(&mut &callback.call[..]) {
+				Ok(call) => call,
+				Err(_) => {
+					Self::deposit_event(Event::CallbackFailed {
+						request: request_id,
+						error: Error::<T>::CallbackUndecodable.into(),
+					});
+					return Weight::zero()
+				},
+			};
+
+			let origin = RawOrigin::Request(request_id).into();
+			match with_storage_layer(|| call.dispatch(origin)) {
+				Ok(post_info) => {
+					Self::deposit_event(Event::CallbackDispatched { request: request_id });
+					post_info.actual_weight.unwrap_or(callback.weight)
+				},
+				Err(err) => {
+					Self::deposit_event(Event::CallbackFailed {
+						request: request_id,
+						error: err.error,
+					});
+					err.post_info.actual_weight.unwrap_or(callback.weight)
+				},
+			}
+		}
 
-			// If a request for proof of the program exists, the submitter needs to receive the
-			// designated reward
-			if let Some(proof_request) = ProofRequests::<T>::get(image_id) {
-				T::Currency::repatriate_reserved(
+		/// Remove a request from the requests scheduled to expire at some block
+		fn unschedule(
+			maybe_expiring: &mut Option<BoundedVec<RequestId, T::MaxExpiringPerBlock>>,
+			request_id: RequestId,
+		) {
+			if let Some(expiring) = maybe_expiring {
+				expiring.retain(|id| *id != request_id);
+				if expiring.is_empty() {
+					*maybe_expiring = None;
+				}
+			}
+		}
+
+		/// Close the auction of a request. Unrevealed bids are forfeited to the requester, and the
+		/// lowest bid claims the request at its price, its deposit becoming the claim collateral.
+		/// Returns the number of forfeited bids.
+		fn close_auction(request_id: RequestId) -> u32 {
+			let auction = match Auctions::<T>::take(request_id) {
+				Some(auction) => auction,
+				None => return 0,
+			};
+			let mut proof_request = match ProofRequests::<T>::get(request_id) {
+				Some(proof_request) => proof_request,
+				None => return 0,
+			};
+
+			let mut forfeited = 0;
+			for (prover, bid) in Bids::<T>::drain_prefix(request_id) {
+				let unslashed = T::Currency::repatriate_reserved(
+					&prover,
 					&proof_request.requester,
-					&who,
-					proof_request.reward,
+					bid.deposit,
 					BalanceStatus::Free,
-				)?;
+				)
+				.unwrap_or(bid.deposit);
+				Self::deposit_event(Event::BidForfeited {
+					request: request_id,
+					prover,
+					slashed: bid.deposit.saturating_sub(unslashed),
+				});
+				forfeited += 1;
 			}
 
+			let winner = auction.lowest_bid.map(|bid| {
+				T::Currency::unreserve(
+					&proof_request.requester,
+					proof_request.reward.saturating_sub(bid.price),
+				);
+				proof_request.reward = bid.price;
+
+				// Should the claim not fit in its lapsing block, it lapses at the deadline instead
+				let mut expires_at = frame_system::Pallet::<T>::block_number()
+					.saturating_add(T::ClaimWindow::get())
+					.min(proof_request.deadline);
+				if ClaimExpiries::<T>::try_append(expires_at, request_id).is_err() {
+					expires_at = proof_request.deadline;
+				}
+				proof_request.claim =
+					Some(Claim { prover: bid.prover.clone(), expires_at, collateral: bid.deposit });
+
+				Self::deposit_event(Event::RequestClaimed {
+					request: request_id,
+					prover: bid.prover.clone(),
+					expires_at,
+				});
+				bid.prover
+			});
+
+			Self::deposit_event(Event::AuctionClosed {
+				request: request_id,
+				winner,
+				reward: proof_request.reward,
+			});
+			ProofRequests::<T>::insert(request_id, proof_request);
+			forfeited
+		}
+
+		/// Give the collateral of a prover which did not fulfill the request it claimed in time,
+		/// and `LapseSlash` of its stake, to the requester, and reopen the request
+		fn lapse_claim(request_id: RequestId) {
+			ProofRequests::<T>::mutate(request_id, |maybe_request| {
+				if let Some(proof_request) = maybe_request {
+					if let Some(claim) = proof_request.claim.take() {
+						// Slashing is best effort: whatever is left of the collateral goes to the
+						// requester
+						let unslashed = T::Currency::repatriate_reserved(
+							&claim.prover,
+							&proof_request.requester,
+							claim.collateral,
+							BalanceStatus::Free,
+						)
+						.unwrap_or(claim.collateral);
+						let stake_slashed =
+							Self::slash_stake(&claim.prover, &proof_request.requester);
+
+						Self::deposit_event(Event::ClaimLapsed {
+							request: request_id,
+							prover: claim.prover,
+							slashed: claim.collateral.saturating_sub(unslashed),
+							stake_slashed,
+						});
+					}
+				}
+			});
+		}
+
+		/// Give `LapseSlash` of the stake of a prover to `beneficiary`, returning the amount
+		/// slashed
+		fn slash_stake(prover: &T::AccountId, beneficiary: &T::AccountId) -> BalanceOf<T> {
+			Provers::<T>::mutate(prover, |maybe_info| {
+				let info = match maybe_info {
+					Some(info) => info,
+					None => return Zero::zero(),
+				};
+				let amount = T::LapseSlash::get() * info.stake;
+				let unslashed = T::Currency::repatriate_reserved(
+					prover,
+					beneficiary,
+					amount,
+					BalanceStatus::Free,
+				)
+				.unwrap_or(amount);
+				let slashed = amount.saturating_sub(unslashed);
+				info.stake = info.stake.saturating_sub(slashed);
+				slashed
+			})
+		}
+
+		/// Pay out the reward of a request whose proof has been verified, and close it
+		fn settle_request(
+			request_id: RequestId,
+			proof_request: ProofRequest<T>,
+			prover: T::AccountId,
+		) -> DispatchResult {
+			Self::close_request(request_id, &proof_request);
+
+			let unpaid = T::Currency::repatriate_reserved(
+				&proof_request.requester,
+				&prover,
+				proof_request.reward,
+				BalanceStatus::Free,
+			)?;
+			ensure!(unpaid.is_zero(), Error::<T>::RewardNotReserved);
+			if let Some(claim) = &proof_request.claim {
+				T::Currency::unreserve(&claim.prover, claim.collateral);
+			}
+
+			FulfilledRequests::<T>::insert(
+				request_id,
+				FulfilledRequest {
+					image_id: proof_request.image_id,
+					prover: prover.clone(),
+					reward: proof_request.reward,
+					fulfilled_at: frame_system::Pallet::<T>::block_number(),
+				},
+			);
+
+			Self::deposit_event(Event::RequestFulfilled {
+				request: request_id,
+				prover,
+				reward: proof_request.reward,
+			});
+			Ok(())
+		}
+	}
+	impl<T: Config> ProofVerifier for Pallet<T> {
+		fn verify_receipt(
+			image_id: ImageId,
+			receipt_data: Vec<(Vec<u32>, u32)>,
+			journal: Vec<u8>,
+		) -> DispatchResult {
+			ensure!(
+				receipt_data.len() <= T::MaxSegments::get() as usize,
+				Error::<T>::TooManySegments
+			);
 			let segments: Vec<SegmentReceipt> = receipt_data
-				.clone()
 				.into_iter()
 				.map(|(seal, index)| SegmentReceipt { seal, index })
 				.collect();
 
 			let receipt = SessionReceipt { segments, journal };
 			receipt.verify(image_id).map_err(|_| Error::<T>::ProofNotVerified)?;
-
-			Self::deposit_event(Event::<T>::ProofVerified);
-
-			// TODO: Also see if there is some image id verification
-			ProofsByImage::<T>::insert(image_id, receipt_data);
 			Ok(())
 		}
+
+		fn verify_receipt_weight(segments: u32) -> Weight {
+			T::WeightInfo::verify_receipt(segments)
+		}
 	}
 }
@@ -0,0 +1,453 @@
+//! Storage migrations for the prover-mgmt pallet.
+
+use super::*;
+use frame_support::{
+	inherent::Vec,
+	pallet_prelude::*,
+	storage_alias,
+	traits::{OnRuntimeUpgrade, ReservableCurrency},
+};
+
+/// Migrate the pallet from unbounded storage to bounded storage types.
+pub mod v1 {
+	use super::*;
+
+	mod v0 {
+		use super::*;
+
+		#[derive(Decode, Encode)]
+		pub struct ProofRequest<AccountId, Balance> {
+			pub requester: AccountId,
+			pub reward: Balance,
+			pub args: Vec<Vec<u32>>,
+		}
+
+		/// Proof requests were keyed by the image id of the program to prove
+		#[storage_alias]
+		pub type ProofRequests<T: Config> = StorageMap<
+			Pallet<T>,
+			Blake2_128Concat,
+			ImageId,
+			ProofRequest<<T as frame_system::Config>::AccountId, BalanceOf<T>>,
+		>;
+	}
+
+	/// Proofs were stored without their journal
+	#[storage_alias]
+	pub type ProofsByImage<T: Config> =
+		StorageMap<Pallet<T>, Blake2_128Concat, ImageId, ReceiptOf<T>>;
+
+	/// Bounds `Programs` and `ProofsByImage`, dropping any entry which does not fit the bounds,
+	/// and returns the rewards of requests made before request ids existed.
+	pub struct MigrateToV1<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let onchain_version = Pallet::<T>::on_chain_storage_version();
+			if onchain_version != 0 {
+				log::info!(
+					target: LOG_TARGET,
+					"skipping v1 migration: on-chain storage version is {:?}",
+					onchain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut reads = 1u64;
+			let mut writes = 0u64;
+
+			// Old requests have no request id, deadline or input commitment, so they cannot be
+			// carried over. Their requesters get their rewards back instead.
+			for (_, request) in v0::ProofRequests::<T>::drain() {
+				T::Currency::unreserve(&request.requester, request.reward);
+				reads += 2;
+				writes += 2;
+			}
+
+			Programs::<T>::translate::<Vec<u8>, _>(|image_id, program| {
+				reads += 1;
+				writes += 1;
+				ProgramOf::<T>::try_from(program)
+					.map_err(|_| {
+						log::warn!(target: LOG_TARGET, "dropping oversized program {:?}", image_id)
+					})
+					.ok()
+			});
+
+			v1::ProofsByImage::<T>::translate::<Vec<(Vec<u32>, u32)>, _>(
+				|image_id, receipt_data| {
+					reads += 1;
+					writes += 1;
+					Pallet::<T>::bound_receipt(receipt_data)
+						.map_err(|_| {
+							log::warn!(
+								target: LOG_TARGET,
+								"dropping oversized proof of {:?}",
+								image_id
+							)
+						})
+						.ok()
+				},
+			);
+
+			StorageVersion::new(1).put::<Pallet<T>>();
+			writes += 1;
+
+			log::info!(target: LOG_TARGET, "migrated prover-mgmt storage to v1");
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			let programs = Programs::<T>::iter_keys().count() as u32;
+			let proofs = v1::ProofsByImage::<T>::iter_keys().count() as u32;
+			Ok((Pallet::<T>::on_chain_storage_version(), programs, proofs).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let (version, programs, proofs): (StorageVersion, u32, u32) =
+				Decode::decode(&mut &state[..]).map_err(|_| "invalid v1 pre-upgrade state")?;
+			if version != 0 {
+				return Ok(())
+			}
+
+			ensure!(Pallet::<T>::on_chain_storage_version() == 1, "storage version not set to 1");
+			ensure!(
+				v0::ProofRequests::<T>::iter_keys().next().is_none(),
+				"unkeyed proof requests left"
+			);
+			// Entries which did not fit the bounds were dropped, and the others must decode
+			ensure!(
+				Programs::<T>::iter_values().count() as u32 <= programs,
+				"more programs than before the migration"
+			);
+			ensure!(
+				v1::ProofsByImage::<T>::iter_values().count() as u32 <= proofs,
+				"more proofs than before the migration"
+			);
+			Ok(())
+		}
+	}
+}
+
+/// Migrate the pallet to storing the journal of every proof.
+pub mod v2 {
+	use super::*;
+
+	/// Drops the proofs stored before journals were kept, as their journal cannot be recovered.
+	pub struct MigrateToV2<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let onchain_version = Pallet::<T>::on_chain_storage_version();
+			if onchain_version != 1 {
+				log::info!(
+					target: LOG_TARGET,
+					"skipping v2 migration: on-chain storage version is {:?}",
+					onchain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			let dropped = v1::ProofsByImage::<T>::drain().count() as u64;
+			if dropped > 0 {
+				log::warn!(target: LOG_TARGET, "dropped {} proofs without a journal", dropped);
+			}
+
+			StorageVersion::new(2).put::<Pallet<T>>();
+
+			log::info!(target: LOG_TARGET, "migrated prover-mgmt storage to v2");
+			T::DbWeight::get().reads_writes(dropped + 1, dropped + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			Ok(Pallet::<T>::on_chain_storage_version().encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let version = StorageVersion::decode(&mut &state[..])
+				.map_err(|_| "invalid v2 pre-upgrade state")?;
+			if version != 1 {
+				return Ok(())
+			}
+
+			ensure!(Pallet::<T>::on_chain_storage_version() == 2, "storage version not set to 2");
+			ensure!(
+				v1::ProofsByImage::<T>::iter_keys().next().is_none(),
+				"proofs without a journal left"
+			);
+			Ok(())
+		}
+	}
+}
+
+/// Migrate the pallet to keeping a history of proofs under unique proof ids.
+pub mod v3 {
+	use super::*;
+
+	mod v2 {
+		use super::*;
+
+		#[derive(Decode, Encode)]
+		pub struct StoredProof<T: Config> {
+			pub receipt: ReceiptOf<T>,
+			pub journal: JournalOf<T>,
+			pub prover: T::AccountId,
+			pub request: Option<RequestId>,
+			pub verified_at: T::BlockNumber,
+		}
+
+		/// Only the latest proof of each image id was stored
+		#[storage_alias]
+		pub type ProofsByImage<T: Config> =
+			StorageMap<Pallet<T>, Blake2_128Concat, ImageId, StoredProof<T>>;
+	}
+
+	/// Moves the proof stored for each image id into `Proofs`, assigning it a proof id and
+	/// indexing it.
+	pub struct MigrateToV3<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let onchain_version = Pallet::<T>::on_chain_storage_version();
+			if onchain_version != 2 {
+				log::info!(
+					target: LOG_TARGET,
+					"skipping v3 migration: on-chain storage version is {:?}",
+					onchain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			// The new index shares its prefix with the old map, so the old map is drained first
+			let old_proofs: Vec<_> = v2::ProofsByImage::<T>::drain().collect();
+			let migrated = old_proofs.len() as u64;
+			for (image_id, proof) in old_proofs {
+				let stored = Pallet::<T>::store_proof(StoredProof {
+					image_id,
+					receipt: proof.receipt,
+					journal: proof.journal,
+					prover: proof.prover,
+					request: proof.request,
+					verified_at: proof.verified_at,
+				});
+				if stored.is_err() {
+					log::warn!(target: LOG_TARGET, "dropping proof of {:?}", image_id);
+				}
+			}
+
+			StorageVersion::new(3).put::<Pallet<T>>();
+
+			log::info!(target: LOG_TARGET, "migrated {} proofs to v3", migrated);
+			T::DbWeight::get().reads_writes(2 * migrated + 1, 6 * migrated + 2)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			let proofs = v2::ProofsByImage::<T>::iter_keys().count() as u64;
+			Ok((Pallet::<T>::on_chain_storage_version(), proofs).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let (version, proofs): (StorageVersion, u64) =
+				Decode::decode(&mut &state[..]).map_err(|_| "invalid v3 pre-upgrade state")?;
+			if version != 2 {
+				return Ok(())
+			}
+
+			ensure!(Pallet::<T>::on_chain_storage_version() == 3, "storage version not set to 3");
+			ensure!(NextProofId::<T>::get() == proofs, "not every proof was assigned an id");
+			ensure!(Proofs::<T>::iter_keys().count() as u64 == proofs, "not every proof was moved");
+			ensure!(
+				LatestProofByImage::<T>::iter_keys().count() as u64 == proofs,
+				"not every image id points to its proof"
+			);
+			Ok(())
+		}
+	}
+}
+
+/// Migrate the pallet to recording the owner of every program.
+pub mod v4 {
+	use super::*;
+	use frame_support::sp_runtime::traits::{Saturating, Zero};
+
+	mod v3 {
+		use super::*;
+
+		#[derive(Decode)]
+		pub struct ProgramInfo<T: Config> {
+			pub owner: T::AccountId,
+			pub deposit: BalanceOf<T>,
+			pub uploaded_at: T::BlockNumber,
+			pub size: u32,
+			pub name: BoundedVec<u8, T::MaxNameLength>,
+			pub description: BoundedVec<u8, T::MaxDescriptionLength>,
+			pub source_hash: Option<[u8; 32]>,
+			pub challenge_period_end: T::BlockNumber,
+		}
+	}
+
+	/// Makes the owner of every program optional, and backfills `ProgramInfos` for the programs
+	/// uploaded before it existed. These have no owner and no deposit, so that root manages
+	/// them, and their image id may be challenged for a full `ChallengePeriod` from the upgrade.
+	pub struct MigrateToV4<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV4<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let onchain_version = Pallet::<T>::on_chain_storage_version();
+			if onchain_version != 3 {
+				log::info!(
+					target: LOG_TARGET,
+					"skipping v4 migration: on-chain storage version is {:?}",
+					onchain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			let mut reads = 1u64;
+			let mut writes = 0u64;
+
+			ProgramInfos::<T>::translate::<v3::ProgramInfo<T>, _>(|_, info| {
+				reads += 1;
+				writes += 1;
+				Some(ProgramInfo {
+					owner: Some(info.owner),
+					deposit: info.deposit,
+					uploaded_at: info.uploaded_at,
+					size: info.size,
+					name: info.name,
+					description: info.description,
+					source_hash: info.source_hash,
+					challenge_period_end: info.challenge_period_end,
+				})
+			});
+
+			let now = frame_system::Pallet::<T>::block_number();
+			let mut backfilled = 0u64;
+			for (image_id, program) in Programs::<T>::iter() {
+				reads += 2;
+				if ProgramInfos::<T>::contains_key(image_id) {
+					continue
+				}
+				ProgramInfos::<T>::insert(
+					image_id,
+					ProgramInfo {
+						owner: None,
+						deposit: Zero::zero(),
+						uploaded_at: now,
+						size: program.len() as u32,
+						name: Default::default(),
+						description: Default::default(),
+						source_hash: None,
+						challenge_period_end: now.saturating_add(T::ChallengePeriod::get()),
+					},
+				);
+				writes += 1;
+				backfilled += 1;
+			}
+
+			StorageVersion::new(4).put::<Pallet<T>>();
+			writes += 1;
+
+			log::info!(target: LOG_TARGET, "backfilled {} program infos in v4", backfilled);
+			T::DbWeight::get().reads_writes(reads, writes)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			let programs = Programs::<T>::iter_keys().count() as u32;
+			Ok((Pallet::<T>::on_chain_storage_version(), programs).encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let (version, programs): (StorageVersion, u32) =
+				Decode::decode(&mut &state[..]).map_err(|_| "invalid v4 pre-upgrade state")?;
+			if version != 3 {
+				return Ok(())
+			}
+
+			ensure!(Pallet::<T>::on_chain_storage_version() == 4, "storage version not set to 4");
+			ensure!(
+				Programs::<T>::iter_keys()
+					.all(|image_id| ProgramInfos::<T>::contains_key(image_id)),
+				"program without info"
+			);
+			ensure!(
+				ProgramInfos::<T>::iter_values().count() as u32 >= programs,
+				"program infos failed to decode"
+			);
+			Ok(())
+		}
+	}
+}
+
+/// Migrate the pallet to indexing stored proofs by their journal.
+pub mod v5 {
+	use super::*;
+	use frame_support::sp_io::hashing::sha2_256;
+
+	/// Indexes every stored proof under the SHA-256 digest of its journal in `ProofByJournal`.
+	pub struct MigrateToV5<T>(PhantomData<T>);
+
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV5<T> {
+		fn on_runtime_upgrade() -> Weight {
+			let onchain_version = Pallet::<T>::on_chain_storage_version();
+			if onchain_version != 4 {
+				log::info!(
+					target: LOG_TARGET,
+					"skipping v5 migration: on-chain storage version is {:?}",
+					onchain_version
+				);
+				return T::DbWeight::get().reads(1)
+			}
+
+			// Proofs are visited in no particular order, so the index of a journal proven more
+			// than once is set to the highest of its proof ids
+			let mut indexed = 0u64;
+			for (proof_id, proof) in Proofs::<T>::iter() {
+				let journal_hash = sha2_256(&proof.journal);
+				ProofByJournal::<T>::mutate(proof.image_id, journal_hash, |latest| {
+					if latest.map_or(true, |latest| latest < proof_id) {
+						*latest = Some(proof_id);
+					}
+				});
+				indexed += 1;
+			}
+
+			StorageVersion::new(5).put::<Pallet<T>>();
+
+			log::info!(target: LOG_TARGET, "indexed {} proofs by journal in v5", indexed);
+			T::DbWeight::get().reads_writes(2 * indexed + 1, indexed + 1)
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			Ok(Pallet::<T>::on_chain_storage_version().encode())
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			let version = StorageVersion::decode(&mut &state[..])
+				.map_err(|_| "invalid v5 pre-upgrade state")?;
+			if version != 4 {
+				return Ok(())
+			}
+
+			ensure!(Pallet::<T>::on_chain_storage_version() == 5, "storage version not set to 5");
+			ensure!(
+				Proofs::<T>::iter().all(|(_, proof)| {
+					let journal_hash = sha2_256(&proof.journal);
+					ProofByJournal::<T>::contains_key(proof.image_id, journal_hash)
+				}),
+				"proof not indexed by its journal"
+			);
+			Ok(())
+		}
+	}
+}
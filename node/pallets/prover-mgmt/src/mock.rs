@@ -1 +1,229 @@
+use crate as pallet_prover_mgmt;
+use crate::{ImageId, OnProofVerified, PruningPolicy, RequestId};
+use frame_support::{
+	parameter_types,
+	traits::{ConstU16, ConstU32, ConstU64},
+	weights::{Weight, WeightToFee},
+};
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+	Perbill,
+};
 
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+
+pub const REQUESTER: u64 = 1;
+pub const PROVER: u64 = 2;
+pub const OTHER_PROVER: u64 = 3;
+pub const OWNER: u64 = 4;
+pub const INITIAL_BALANCE: u64 = 1_000_000;
+
+pub const DEPOSIT_BASE: u64 = 10;
+pub const MIN_PROVER_STAKE: u64 = 100;
+pub const CLAIM_WINDOW: u64 = 5;
+pub const CLAIM_COLLATERAL: u64 = 20;
+pub const CHALLENGE_PERIOD: u64 = 10;
+pub const BIDDING_PERIOD: u64 = 3;
+pub const REVEAL_PERIOD: u64 = 3;
+pub const BID_DEPOSIT: u64 = 5;
+pub const CALLBACK_FEE: u64 = 7;
+pub const MAX_SEGMENTS: u32 = 4;
+
+// Configure a mock runtime to test the pallet.
+frame_support::construct_runtime!(
+	pub enum Test where
+		Block = Block,
+		NodeBlock = Block,
+		UncheckedExtrinsic = UncheckedExtrinsic,
+	{
+		System: frame_system,
+		Balances: pallet_balances,
+		ProverMgmt: pallet_prover_mgmt,
+		Callback: callback,
+	}
+);
+
+impl frame_system::Config for Test {
+	type BaseCallFilter = frame_support::traits::Everything;
+	type BlockWeights = ();
+	type BlockLength = ();
+	type DbWeight = ();
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type RuntimeEvent = RuntimeEvent;
+	type BlockHashCount = ConstU64<250>;
+	type Version = ();
+	type PalletInfo = PalletInfo;
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+	type SS58Prefix = ConstU16<42>;
+	type OnSetCode = ();
+	type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type RuntimeEvent = RuntimeEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<1>;
+	type AccountStore = System;
+	type WeightInfo = ();
+	type FreezeIdentifier = ();
+	type MaxFreezes = ();
+	type HoldIdentifier = ();
+	type MaxHolds = ();
+}
+
+/// Charges the same fee for the weight of any callback
+pub struct FixedFee;
+
+impl WeightToFee for FixedFee {
+	type Balance = u64;
+
+	fn weight_to_fee(_weight: &Weight) -> u64 {
+		CALLBACK_FEE
+	}
+}
+
+parameter_types! {
+	pub storage ProofPruning: PruningPolicy<u64> = PruningPolicy::KeepAll;
+	pub const MaxCallbackWeight: Weight = Weight::from_parts(1_000_000_000, 1024 * 1024);
+	pub const LapseSlash: Perbill = Perbill::from_percent(10);
+	pub storage VerifiedProofs: Vec<(ImageId, Option<RequestId>, u64)> = Vec::new();
+}
+
+/// Records the proofs the pallet passes to its `OnProofVerified` hook
+pub struct RecordProofs;
+
+impl OnProofVerified<u64> for RecordProofs {
+	fn on_proof_verified(
+		image_id: ImageId,
+		request: Option<RequestId>,
+		_journal: &[u8],
+		prover: &u64,
+	) {
+		let mut proofs = VerifiedProofs::get();
+		proofs.push((image_id, request, *prover));
+		VerifiedProofs::set(&proofs);
+	}
+
+	fn weight() -> Weight {
+		Weight::zero()
+	}
+}
+
+impl pallet_prover_mgmt::Config for Test {
+	type Currency = Balances;
+	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
+	type WeightInfo = ();
+	type MaxArgsLength = ConstU32<16>;
+	type MaxArgs = ConstU32<4>;
+	type MaxProgramLength = ConstU32<{ 64 * 1024 }>;
+	type MaxProofLength = ConstU32<{ 64 * 1024 }>;
+	type MaxJournalLength = ConstU32<128>;
+	type MaxSegments = ConstU32<MAX_SEGMENTS>;
+	type MaxRequestLifetime = ConstU64<100>;
+	type MaxExpiringPerBlock = ConstU32<50>;
+	type DepositBase = ConstU64<DEPOSIT_BASE>;
+	type DepositPerByte = ConstU64<1>;
+	type MaxNameLength = ConstU32<16>;
+	type MaxDescriptionLength = ConstU32<32>;
+	type MaxManifestLength = ConstU32<64>;
+	type ChallengePeriod = ConstU64<CHALLENGE_PERIOD>;
+	type MinProverStake = ConstU64<MIN_PROVER_STAKE>;
+	type UnbondingPeriod = ConstU64<20>;
+	type MaxVersionLength = ConstU32<8>;
+	type ClaimWindow = ConstU64<CLAIM_WINDOW>;
+	type ClaimCollateral = ConstU64<CLAIM_COLLATERAL>;
+	type LapseSlash = LapseSlash;
+	type BiddingPeriod = ConstU64<BIDDING_PERIOD>;
+	type RevealPeriod = ConstU64<REVEAL_PERIOD>;
+	type BidDeposit = ConstU64<BID_DEPOSIT>;
+	type MaxBids = ConstU32<32>;
+	type ProofPruning = ProofPruning;
+	type MaxProofsPrunedPerBlock = ConstU32<50>;
+	type OnProofVerified = RecordProofs;
+	type WeightToFee = FixedFee;
+	type MaxCallbackLength = ConstU32<64>;
+	type MaxCallbackWeight = MaxCallbackWeight;
+}
+
+impl callback::Config for Test {
+	type RequestOrigin = pallet_prover_mgmt::EnsureRequest;
+}
+
+/// Target of the callbacks of requests
+#[frame_support::pallet]
+pub mod callback {
+	use crate::RequestId;
+	use frame_support::pallet_prelude::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		type RequestOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = RequestId>;
+	}
+
+	#[pallet::pallet]
+	#[pallet::without_storage_info]
+	pub struct Pallet<T>(_);
+
+	#[pallet::storage]
+	/// Requests whose callback was dispatched, and not reverted
+	pub type Received<T> = StorageValue<_, Vec<RequestId>, ValueQuery>;
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		#[pallet::call_index(0)]
+		#[pallet::weight(Weight::from_parts(1_000, 0))]
+		pub fn record(origin: OriginFor<T>) -> DispatchResult {
+			let request_id = T::RequestOrigin::ensure_origin(origin)?;
+			Received::<T>::append(request_id);
+			Ok(())
+		}
+
+		/// Fails after writing to storage, which must be reverted
+		#[pallet::call_index(1)]
+		#[pallet::weight(Weight::from_parts(1_000, 0))]
+		pub fn fail(origin: OriginFor<T>) -> DispatchResult {
+			let request_id = T::RequestOrigin::ensure_origin(origin)?;
+			Received::<T>::append(request_id);
+			Err(DispatchError::Other("callback failed"))
+		}
+	}
+}
+
+// Build genesis storage according to the mock runtime.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: [REQUESTER, PROVER, OTHER_PROVER, OWNER]
+			.into_iter()
+			.map(|account| (account, INITIAL_BALANCE))
+			.collect(),
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext: sp_io::TestExternalities = storage.into();
+	// Events are not deposited in the genesis block
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -1 +1,826 @@
+use crate::{
+	fixtures, migrations, mock::*, Auctions, Bids, Config, Error, Event, FulfilledRequests,
+	ImageId, LatestProofByImage, NextProofId, ProgramInfos, Programs, ProofByJournal,
+	ProofByRequest, ProofRequests, Proofs, PruningPolicy, RequestId, WeightInfo,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchResultWithPostInfo, GetDispatchInfo},
+	storage::{storage_prefix, unhashed},
+	traits::{Get, GetStorageVersion, Hooks, OnRuntimeUpgrade, ReservableCurrency, StorageVersion},
+	Blake2_128Concat, StorageHasher,
+};
+use sp_runtime::DispatchError;
 
+const PROGRAM: [u8; 10] = [7; 10];
+const REWARD: u64 = 500;
+
+fn free(who: u64) -> u64 {
+	Balances::free_balance(who)
+}
+
+fn reserved(who: u64) -> u64 {
+	Balances::reserved_balance(who)
+}
+
+/// The image id of the fixture, under which a stand-in program is uploaded
+fn image_id() -> ImageId {
+	fixtures::factors().image_id
+}
+
+fn upload() {
+	assert_ok!(ProverMgmt::upload_program(
+		RuntimeOrigin::signed(OWNER),
+		image_id(),
+		PROGRAM.to_vec(),
+		b"name".to_vec(),
+		b"description".to_vec(),
+		None,
+		None,
+	));
+}
+
+fn request_with_args(args: Vec<Vec<u32>>, callback: Option<RuntimeCall>) -> RequestId {
+	let request_id = crate::NextRequestId::<Test>::get();
+	assert_ok!(ProverMgmt::request_proof(
+		RuntimeOrigin::signed(REQUESTER),
+		image_id(),
+		args,
+		REWARD,
+		50,
+		callback.map(Box::new),
+	));
+	request_id
+}
+
+/// Request a proof which the fixture fulfills
+fn request(callback: Option<RuntimeCall>) -> RequestId {
+	request_with_args(fixtures::factors_args(), callback)
+}
+
+fn register(prover: u64) {
+	assert_ok!(ProverMgmt::register_prover(
+		RuntimeOrigin::signed(prover),
+		MIN_PROVER_STAKE,
+		1 << 20,
+		b"0.14.0".to_vec(),
+	));
+}
+
+/// Submit the fixture receipt, fulfilling `request_id` if any
+fn submit(prover: u64, request_id: Option<RequestId>) -> DispatchResultWithPostInfo {
+	let fixture = fixtures::factors();
+	ProverMgmt::store_and_verify_proof(
+		RuntimeOrigin::signed(prover),
+		fixture.image_id,
+		request_id,
+		fixture.receipt_data,
+		fixture.journal,
+	)
+}
+
+fn fulfill(prover: u64, request_id: RequestId) {
+	assert_ok!(submit(prover, Some(request_id)));
+}
+
+/// The key of an entry of a storage map which a migration removes, hashed with
+/// `Blake2_128Concat`
+fn map_key(storage: &[u8], key: impl Encode) -> Vec<u8> {
+	let mut map_key = storage_prefix(b"ProverMgmt", storage).to_vec();
+	map_key.extend(Blake2_128Concat::hash(&key.encode()));
+	map_key
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		ProverMgmt::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn verified_proof_pays_reward() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+		assert_eq!(reserved(REQUESTER), REWARD);
+
+		fulfill(PROVER, request_id);
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE - REWARD);
+		assert_eq!(reserved(REQUESTER), 0);
+		assert_eq!(free(PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE + REWARD);
+		assert!(ProofRequests::<Test>::get(request_id).is_none());
+		assert!(FulfilledRequests::<Test>::contains_key(request_id));
+		System::assert_has_event(
+			Event::RequestFulfilled { request: request_id, prover: PROVER, reward: REWARD }.into(),
+		);
+	});
+}
+
+#[test]
+fn unverified_proof_leaves_request_open() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+
+		// A journal claiming another product, behind the right input commitment
+		let mut fixture = fixtures::factors();
+		*fixture.journal.last_mut().unwrap() ^= 1;
+		assert_noop!(
+			ProverMgmt::store_and_verify_proof(
+				RuntimeOrigin::signed(PROVER),
+				fixture.image_id,
+				Some(request_id),
+				fixture.receipt_data,
+				fixture.journal,
+			),
+			Error::<Test>::ProofNotVerified
+		);
+
+		// A seal which was tampered with
+		let mut fixture = fixtures::factors();
+		let seal = &mut fixture.receipt_data[0].0;
+		let middle = seal.len() / 2;
+		seal[middle] ^= 1;
+		assert_noop!(
+			ProverMgmt::store_and_verify_proof(
+				RuntimeOrigin::signed(PROVER),
+				fixture.image_id,
+				Some(request_id),
+				fixture.receipt_data,
+				fixture.journal,
+			),
+			Error::<Test>::ProofNotVerified
+		);
+		assert_eq!(reserved(REQUESTER), REWARD);
+	});
+}
+
+#[test]
+fn proof_of_unknown_program_is_rejected() {
+	new_test_ext().execute_with(|| {
+		register(PROVER);
+		assert_noop!(submit(PROVER, None), Error::<Test>::ProgramDoesNotExist);
+	});
+}
+
+#[test]
+fn proof_with_too_many_segments_is_rejected() {
+	new_test_ext().execute_with(|| {
+		upload();
+		let fixture = fixtures::factors();
+		let seal = &fixture.receipt_data[0].0;
+		let segments = (0..=MAX_SEGMENTS).map(|index| (seal.clone(), index)).collect();
+		assert_noop!(
+			ProverMgmt::store_and_verify_proof(
+				RuntimeOrigin::signed(PROVER),
+				fixture.image_id,
+				None,
+				segments,
+				fixture.journal,
+			),
+			Error::<Test>::TooManySegments
+		);
+	});
+}
+
+#[test]
+fn proof_must_commit_to_request_args() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		// The fixture proves the factors 17 and 23, not 19 and 23
+		let request_id = request_with_args(vec![vec![19, 0], vec![23, 0]], None);
+		assert_noop!(submit(PROVER, Some(request_id)), Error::<Test>::InputCommitmentMismatch);
+	});
+}
+
+#[test]
+fn unregistered_prover_cannot_fulfill_requests() {
+	new_test_ext().execute_with(|| {
+		upload();
+		let request_id = request(None);
+		assert_noop!(submit(PROVER, Some(request_id)), Error::<Test>::NotActiveProver);
+	});
+}
+
+#[test]
+fn request_claimed_by_another_prover_is_rejected() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		register(OTHER_PROVER);
+		let request_id = request(None);
+		assert_ok!(ProverMgmt::claim_request(RuntimeOrigin::signed(PROVER), request_id));
+		assert_noop!(submit(OTHER_PROVER, Some(request_id)), Error::<Test>::RequestAlreadyClaimed);
+	});
+}
+
+#[test]
+fn fulfilled_request_cannot_be_settled_twice() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		register(OTHER_PROVER);
+		let request_id = request(None);
+		fulfill(PROVER, request_id);
+
+		// The request is checked before the receipt is verified
+		assert_noop!(
+			submit(OTHER_PROVER, Some(request_id)),
+			Error::<Test>::RequestAlreadyFulfilled
+		);
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE - REWARD);
+		assert_eq!(free(OTHER_PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE);
+	});
+}
+
+#[test]
+fn expired_request_returns_reward() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+
+		run_to_block(50);
+		System::assert_has_event(Event::RequestExpired { request: request_id }.into());
+		assert!(ProofRequests::<Test>::get(request_id).is_none());
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE);
+		assert_eq!(reserved(REQUESTER), 0);
+		assert_noop!(submit(PROVER, Some(request_id)), Error::<Test>::RequestDoesNotExist);
+	});
+}
+
+#[test]
+fn cancelled_request_returns_reward() {
+	new_test_ext().execute_with(|| {
+		upload();
+		let request_id = request(None);
+		assert_eq!(reserved(REQUESTER), REWARD);
+
+		assert_noop!(
+			ProverMgmt::cancel_request(RuntimeOrigin::signed(PROVER), request_id),
+			Error::<Test>::NotRequester
+		);
+		assert_ok!(ProverMgmt::cancel_request(RuntimeOrigin::signed(REQUESTER), request_id));
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE);
+		assert_eq!(reserved(REQUESTER), 0);
+		System::assert_last_event(Event::RequestCancelled { request: request_id }.into());
+	});
+}
+
+#[test]
+fn migrate_to_v1_refunds_old_requests_and_bounds_programs() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(0).put::<ProverMgmt>();
+		assert_ok!(Balances::reserve(&REQUESTER, REWARD));
+		unhashed::put(
+			&map_key(b"ProofRequests", image_id()),
+			&(REQUESTER, REWARD, fixtures::factors_args()),
+		);
+		unhashed::put(&Programs::<Test>::hashed_key_for(image_id()), &PROGRAM.to_vec());
+		let max_length: u32 = <Test as Config>::MaxProgramLength::get();
+		let too_long = vec![0u8; max_length as usize + 1];
+		unhashed::put(&Programs::<Test>::hashed_key_for([2; 8]), &too_long);
+
+		migrations::v1::MigrateToV1::<Test>::on_runtime_upgrade();
+		assert_eq!(ProverMgmt::on_chain_storage_version(), 1);
+		assert_eq!(reserved(REQUESTER), 0);
+		assert!(unhashed::get_raw(&map_key(b"ProofRequests", image_id())).is_none());
+		assert_eq!(ProverMgmt::program(image_id()), Some(PROGRAM.to_vec()));
+		assert!(ProverMgmt::program([2; 8]).is_none());
+	});
+}
+
+#[test]
+fn upload_reserves_deposit_per_byte() {
+	new_test_ext().execute_with(|| {
+		upload();
+		// The program, its name and its description are charged for
+		let deposit = DEPOSIT_BASE + (PROGRAM.len() + 4 + 11) as u64;
+		assert_eq!(reserved(OWNER), deposit);
+		assert_eq!(free(OWNER), INITIAL_BALANCE - deposit);
+		assert_eq!(ProverMgmt::program_details(image_id()).unwrap().deposit, deposit);
+	});
+}
+
+#[test]
+fn owner_removes_program_and_gets_deposit_back() {
+	new_test_ext().execute_with(|| {
+		upload();
+		assert_eq!(ProverMgmt::program(image_id()), Some(PROGRAM.to_vec()));
+
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_noop!(
+			ProverMgmt::remove_program(RuntimeOrigin::signed(REQUESTER), image_id()),
+			Error::<Test>::NotProgramOwner
+		);
+		assert_ok!(ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), image_id()));
+		assert_eq!(reserved(OWNER), 0);
+		assert_eq!(free(OWNER), INITIAL_BALANCE);
+		assert!(ProverMgmt::program(image_id()).is_none());
+		assert!(ProverMgmt::program_details(image_id()).is_none());
+	});
+}
+
+#[test]
+fn migrate_to_v4_backfills_program_infos() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(3).put::<ProverMgmt>();
+		let legacy: ImageId = [2; 8];
+		unhashed::put(&Programs::<Test>::hashed_key_for(legacy), &PROGRAM.to_vec());
+		unhashed::put(&Programs::<Test>::hashed_key_for(image_id()), &PROGRAM.to_vec());
+		let old_info =
+			(OWNER, 25u64, 1u64, 10u32, b"name".to_vec(), Vec::<u8>::new(), None::<[u8; 32]>, 1u64);
+		unhashed::put(&ProgramInfos::<Test>::hashed_key_for(image_id()), &old_info);
+		assert_ok!(Balances::reserve(&OWNER, 25));
+
+		migrations::v4::MigrateToV4::<Test>::on_runtime_upgrade();
+		assert_eq!(ProverMgmt::on_chain_storage_version(), 4);
+		let details = ProverMgmt::program_details(image_id()).unwrap();
+		assert_eq!((details.owner, details.deposit), (Some(OWNER), 25));
+		let details = ProverMgmt::program_details(legacy).unwrap();
+		assert_eq!((details.owner, details.deposit), (None, 0));
+		assert_eq!(details.size, PROGRAM.len() as u32);
+
+		// Legacy programs are managed by root once their challenge period is over
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_noop!(
+			ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), legacy),
+			Error::<Test>::NotProgramOwner
+		);
+		assert_ok!(ProverMgmt::transfer_program_ownership(RuntimeOrigin::root(), legacy, OWNER));
+		assert_ok!(ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), legacy));
+		assert_ok!(ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), image_id()));
+		assert_eq!(reserved(OWNER), 0);
+	});
+}
+
+#[test]
+fn program_cannot_be_removed_during_challenge_period() {
+	new_test_ext().execute_with(|| {
+		upload();
+		// The owner can not escape a challenge by removing the program
+		assert_noop!(
+			ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), image_id()),
+			Error::<Test>::ChallengePeriodNotOver
+		);
+		run_to_block(CHALLENGE_PERIOD);
+		assert_noop!(
+			ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), image_id()),
+			Error::<Test>::ChallengePeriodNotOver
+		);
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_ok!(ProverMgmt::remove_program(RuntimeOrigin::signed(OWNER), image_id()));
+	});
+}
+
+#[test]
+fn program_not_matching_its_image_id_is_removed_on_challenge() {
+	new_test_ext().execute_with(|| {
+		// The stand-in program is not an ELF, so it can not match the fixture's image id
+		upload();
+		let deposit = reserved(OWNER);
+		assert_ok!(ProverMgmt::challenge_image_id(RuntimeOrigin::signed(REQUESTER), image_id()));
+		System::assert_last_event(
+			Event::FraudulentProgramRemoved {
+				image_id: image_id(),
+				owner: Some(OWNER),
+				challenger: REQUESTER,
+				slashed: deposit,
+			}
+			.into(),
+		);
+		assert!(ProverMgmt::program(image_id()).is_none());
+		assert_eq!(free(OWNER), INITIAL_BALANCE - deposit);
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE + deposit);
+	});
+}
+
+#[test]
+fn matching_program_survives_challenge() {
+	new_test_ext().execute_with(|| {
+		let fixture = fixtures::factors();
+		assert_ok!(ProverMgmt::upload_program(
+			RuntimeOrigin::signed(OWNER),
+			fixture.image_id,
+			fixtures::factors_program(),
+			b"name".to_vec(),
+			b"description".to_vec(),
+			None,
+			None,
+		));
+		assert_noop!(
+			ProverMgmt::challenge_image_id(RuntimeOrigin::signed(REQUESTER), fixture.image_id),
+			Error::<Test>::ImageIdMatches
+		);
+
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_noop!(
+			ProverMgmt::challenge_image_id(RuntimeOrigin::signed(REQUESTER), fixture.image_id),
+			Error::<Test>::ChallengePeriodOver
+		);
+	});
+}
+
+#[test]
+fn lapsed_claim_slashes_collateral_and_stake() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+		assert_ok!(ProverMgmt::claim_request(RuntimeOrigin::signed(PROVER), request_id));
+
+		run_to_block(1 + CLAIM_WINDOW);
+		let stake_slashed = LapseSlash::get() * MIN_PROVER_STAKE;
+		System::assert_has_event(
+			Event::ClaimLapsed {
+				request: request_id,
+				prover: PROVER,
+				slashed: CLAIM_COLLATERAL,
+				stake_slashed,
+			}
+			.into(),
+		);
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE - REWARD + CLAIM_COLLATERAL + stake_slashed);
+		assert_eq!(reserved(REQUESTER), REWARD);
+		assert_eq!(free(PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE - CLAIM_COLLATERAL);
+		assert_eq!(reserved(PROVER), MIN_PROVER_STAKE - stake_slashed);
+
+		// The request is open again, and the prover's stake is now below the minimum
+		let details = ProverMgmt::prover_details(&PROVER).unwrap();
+		assert_eq!(details.stake, MIN_PROVER_STAKE - stake_slashed);
+		assert!(!details.active);
+		let open = ProverMgmt::open_requests(None, request_id, 1);
+		assert_eq!(open[0].claim, None);
+		assert_noop!(
+			ProverMgmt::claim_request(RuntimeOrigin::signed(PROVER), request_id),
+			Error::<Test>::NotActiveProver
+		);
+		assert_noop!(submit(PROVER, Some(request_id)), Error::<Test>::NotActiveProver);
+	});
+}
+
+#[test]
+fn fulfilling_claimed_request_returns_collateral() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+
+		assert_ok!(ProverMgmt::claim_request(RuntimeOrigin::signed(PROVER), request_id));
+		assert_eq!(reserved(PROVER), MIN_PROVER_STAKE + CLAIM_COLLATERAL);
+
+		fulfill(PROVER, request_id);
+		assert_eq!(free(PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE + REWARD);
+		assert_eq!(reserved(PROVER), MIN_PROVER_STAKE);
+	});
+}
+
+#[test]
+fn claimed_request_can_not_be_claimed_or_cancelled() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		register(OTHER_PROVER);
+		let request_id = request(None);
+		assert_ok!(ProverMgmt::claim_request(RuntimeOrigin::signed(PROVER), request_id));
+
+		assert_noop!(
+			ProverMgmt::claim_request(RuntimeOrigin::signed(OTHER_PROVER), request_id),
+			Error::<Test>::RequestAlreadyClaimed
+		);
+		assert_noop!(
+			ProverMgmt::cancel_request(RuntimeOrigin::signed(REQUESTER), request_id),
+			Error::<Test>::RequestAlreadyClaimed
+		);
+	});
+}
+
+#[test]
+fn auction_close_refunds_and_forfeits() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		register(OTHER_PROVER);
+		register(OWNER);
+		assert_ok!(ProverMgmt::request_proof_auction(
+			RuntimeOrigin::signed(REQUESTER),
+			image_id(),
+			fixtures::factors_args(),
+			REWARD,
+			50,
+		));
+		let request_id = 0;
+
+		let bid = |prover: u64, price: u64| {
+			let commitment = ProverMgmt::bid_commitment(&prover, price, &[prover as u8; 32]);
+			assert_ok!(ProverMgmt::commit_bid(
+				RuntimeOrigin::signed(prover),
+				request_id,
+				commitment
+			));
+		};
+		bid(PROVER, 300);
+		bid(OTHER_PROVER, 400);
+		// Never revealed
+		bid(OWNER, 100);
+		assert_noop!(
+			ProverMgmt::claim_request(RuntimeOrigin::signed(PROVER), request_id),
+			Error::<Test>::AuctionOngoing
+		);
+		assert_noop!(submit(PROVER, Some(request_id)), Error::<Test>::AuctionOngoing);
+
+		run_to_block(1 + BIDDING_PERIOD);
+		let reveal = |prover: u64, price: u64| {
+			assert_ok!(ProverMgmt::reveal_bid(
+				RuntimeOrigin::signed(prover),
+				request_id,
+				price,
+				[prover as u8; 32],
+			));
+		};
+		reveal(OTHER_PROVER, 400);
+		reveal(PROVER, 300);
+		// The outbid deposit is returned right away
+		assert_eq!(reserved(OTHER_PROVER), MIN_PROVER_STAKE);
+		assert_eq!(reserved(PROVER), MIN_PROVER_STAKE + BID_DEPOSIT);
+
+		run_to_block(1 + BIDDING_PERIOD + REVEAL_PERIOD);
+		assert!(Auctions::<Test>::get(request_id).is_none());
+		assert_eq!(Bids::<Test>::iter_prefix(request_id).count(), 0);
+		System::assert_has_event(
+			Event::BidForfeited { request: request_id, prover: OWNER, slashed: BID_DEPOSIT }.into(),
+		);
+		System::assert_has_event(
+			Event::AuctionClosed { request: request_id, winner: Some(PROVER), reward: 300 }.into(),
+		);
+
+		// The requester gets the forfeited deposit and what is left of the maximum price
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE - 300 + BID_DEPOSIT);
+		assert_eq!(reserved(REQUESTER), 300);
+		assert_eq!(reserved(OWNER), MIN_PROVER_STAKE + DEPOSIT_BASE + 25);
+
+		// The winning deposit is held as the collateral of the winner's claim
+		assert_noop!(submit(OTHER_PROVER, Some(request_id)), Error::<Test>::RequestAlreadyClaimed);
+		fulfill(PROVER, request_id);
+		assert_eq!(free(PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE + 300);
+		assert_eq!(reserved(PROVER), MIN_PROVER_STAKE);
+	});
+}
+
+#[test]
+fn verified_journal_is_stored() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+		let journal = fixtures::factors().journal;
+
+		fulfill(PROVER, request_id);
+		assert_eq!(
+			ProverMgmt::request_journal(request_id).map(|j| j.into_inner()),
+			Some(journal.clone())
+		);
+		let proof_id = ProverMgmt::request_proof_id(request_id).unwrap();
+		System::assert_has_event(
+			Event::ProofVerified {
+				proof: proof_id,
+				image_id: image_id(),
+				request: Some(request_id),
+				prover: PROVER,
+				journal_hash: sp_io::hashing::sha2_256(&journal),
+			}
+			.into(),
+		);
+	});
+}
+
+#[test]
+fn migrate_to_v2_drops_proofs_without_journal() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(1).put::<ProverMgmt>();
+		let receipt: Vec<(Vec<u32>, u32)> = vec![(vec![1, 2, 3], 0)];
+		unhashed::put(&map_key(b"ProofsByImage", image_id()), &receipt);
+
+		migrations::v2::MigrateToV2::<Test>::on_runtime_upgrade();
+		assert_eq!(ProverMgmt::on_chain_storage_version(), 2);
+		assert!(unhashed::get_raw(&map_key(b"ProofsByImage", image_id())).is_none());
+	});
+}
+
+#[test]
+fn pruning_proofs_prunes_fulfilled_requests() {
+	new_test_ext().execute_with(|| {
+		ProofPruning::set(&PruningPolicy::KeepLastOverall(1));
+		upload();
+		register(PROVER);
+		let first = request(None);
+		let second = request(None);
+		fulfill(PROVER, first);
+		fulfill(PROVER, second);
+
+		run_to_block(2);
+		assert!(ProofByRequest::<Test>::get(first).is_none());
+		assert!(!FulfilledRequests::<Test>::contains_key(first));
+		assert!(ProofByRequest::<Test>::get(second).is_some());
+		assert!(FulfilledRequests::<Test>::contains_key(second));
+	});
+}
+
+#[test]
+fn migrate_to_v3_assigns_proof_ids() {
+	new_test_ext().execute_with(|| {
+		StorageVersion::new(2).put::<ProverMgmt>();
+		let fixture = fixtures::factors();
+		let old_proof =
+			(fixture.receipt_data, fixture.journal.clone(), PROVER, Some(0 as RequestId), 1u64);
+		unhashed::put(&map_key(b"ProofsByImage", image_id()), &old_proof);
+
+		migrations::v3::MigrateToV3::<Test>::on_runtime_upgrade();
+		assert_eq!(ProverMgmt::on_chain_storage_version(), 3);
+		assert_eq!(NextProofId::<Test>::get(), 1);
+		assert_eq!(LatestProofByImage::<Test>::get(image_id()), Some(0));
+		assert_eq!(ProofByRequest::<Test>::get(0), Some(0));
+		let proof = Proofs::<Test>::get(0).unwrap();
+		assert_eq!(proof.prover, PROVER);
+		assert_eq!(proof.journal.into_inner(), fixture.journal);
+	});
+}
+
+#[test]
+fn unsubmitted_segments_are_refunded() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+		let fixture = fixtures::factors();
+		let call = crate::Call::<Test>::store_and_verify_proof {
+			image_id: fixture.image_id,
+			request_id: Some(request_id),
+			receipt_data: fixture.receipt_data.clone(),
+			journal: fixture.journal.clone(),
+		};
+
+		let post_info = submit(PROVER, Some(request_id)).unwrap();
+		let segments = fixture.receipt_data.len() as u32;
+		let actual_weight = <() as WeightInfo>::store_and_verify_proof()
+			.saturating_add(<() as WeightInfo>::verify_receipt(segments));
+		assert_eq!(post_info.actual_weight, Some(actual_weight));
+		// Verifying `MaxSegments` is charged up front
+		assert!(segments < MAX_SEGMENTS);
+		assert!(actual_weight.all_lt(call.get_dispatch_info().weight));
+	});
+}
+
+#[test]
+fn verified_proofs_are_passed_to_the_hook() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+
+		assert_ok!(submit(OTHER_PROVER, None));
+		fulfill(PROVER, request_id);
+		assert_eq!(
+			VerifiedProofs::get(),
+			vec![(image_id(), None, OTHER_PROVER), (image_id(), Some(request_id), PROVER)]
+		);
+	});
+}
+
+#[test]
+fn cancelled_request_returns_callback_fee() {
+	new_test_ext().execute_with(|| {
+		upload();
+		let request_id = request(Some(RuntimeCall::Callback(callback::Call::record {})));
+		assert_eq!(reserved(REQUESTER), REWARD + CALLBACK_FEE);
+
+		assert_ok!(ProverMgmt::cancel_request(RuntimeOrigin::signed(REQUESTER), request_id));
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE);
+		assert_eq!(reserved(REQUESTER), 0);
+	});
+}
+
+#[test]
+fn callback_is_dispatched_with_request_origin() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(Some(RuntimeCall::Callback(callback::Call::record {})));
+
+		fulfill(PROVER, request_id);
+		assert_eq!(callback::Received::<Test>::get(), vec![request_id]);
+		System::assert_has_event(Event::CallbackDispatched { request: request_id }.into());
+		// The prepaid fee goes to the prover
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE - REWARD - CALLBACK_FEE);
+		assert_eq!(free(PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE + REWARD + CALLBACK_FEE);
+	});
+}
+
+#[test]
+fn failing_callback_does_not_revert_settlement() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(Some(RuntimeCall::Callback(callback::Call::fail {})));
+
+		fulfill(PROVER, request_id);
+		// The message of an `Other` error is not encoded in events
+		System::assert_has_event(
+			Event::CallbackFailed { request: request_id, error: DispatchError::Other("") }.into(),
+		);
+		// The writes of the callback are reverted, but the request stays settled
+		assert!(callback::Received::<Test>::get().is_empty());
+		assert!(FulfilledRequests::<Test>::contains_key(request_id));
+		assert_eq!(free(REQUESTER), INITIAL_BALANCE - REWARD - CALLBACK_FEE);
+		assert_eq!(free(PROVER), INITIAL_BALANCE - MIN_PROVER_STAKE + REWARD + CALLBACK_FEE);
+	});
+}
+
+#[test]
+fn duplicate_proof_without_request_is_rejected() {
+	new_test_ext().execute_with(|| {
+		ProofPruning::set(&PruningPolicy::KeepLastOverall(1));
+		upload();
+		register(PROVER);
+		assert_ok!(submit(OTHER_PROVER, None));
+		assert_noop!(submit(PROVER, None), Error::<Test>::ProofAlreadyStored);
+
+		// Requests are fulfilled by proofs with the same journal nonetheless
+		let request_id = request(None);
+		fulfill(PROVER, request_id);
+
+		// Pruning the first proof keeps the index pointing at the second one
+		run_to_block(2);
+		assert!(Proofs::<Test>::get(0).is_none());
+		let journal_hash = sp_io::hashing::sha2_256(&fixtures::factors().journal);
+		assert_eq!(ProofByJournal::<Test>::get(image_id(), journal_hash), Some(1));
+		assert_noop!(submit(OTHER_PROVER, None), Error::<Test>::ProofAlreadyStored);
+	});
+}
+
+#[test]
+fn migrate_to_v5_indexes_proofs_by_journal() {
+	new_test_ext().execute_with(|| {
+		upload();
+		register(PROVER);
+		let request_id = request(None);
+		fulfill(PROVER, request_id);
+		let journal_hash = sp_io::hashing::sha2_256(&fixtures::factors().journal);
+		ProofByJournal::<Test>::remove(image_id(), journal_hash);
+		StorageVersion::new(4).put::<ProverMgmt>();
+
+		migrations::v5::MigrateToV5::<Test>::on_runtime_upgrade();
+		assert_eq!(ProverMgmt::on_chain_storage_version(), 5);
+		assert_eq!(ProofByJournal::<Test>::get(image_id(), journal_hash), Some(0));
+		assert_noop!(submit(OTHER_PROVER, None), Error::<Test>::ProofAlreadyStored);
+	});
+}
+
+#[test]
+fn open_requests_are_paged_in_order() {
+	new_test_ext().execute_with(|| {
+		upload();
+		let other: ImageId = [3; 8];
+		assert_ok!(ProverMgmt::upload_program(
+			RuntimeOrigin::signed(OWNER),
+			other,
+			PROGRAM.to_vec(),
+			b"other".to_vec(),
+			b"description".to_vec(),
+			None,
+			None,
+		));
+		let first = request(None);
+		let for_other = crate::NextRequestId::<Test>::get();
+		assert_ok!(ProverMgmt::request_proof(
+			RuntimeOrigin::signed(REQUESTER),
+			other,
+			fixtures::factors_args(),
+			REWARD,
+			50,
+			None,
+		));
+		let cancelled = request(None);
+		let last = request(None);
+		assert_ok!(ProverMgmt::cancel_request(RuntimeOrigin::signed(REQUESTER), cancelled));
+
+		let ids = |image_id: Option<ImageId>, from: RequestId, limit: u32| -> Vec<RequestId> {
+			ProverMgmt::open_requests(image_id, from, limit)
+				.into_iter()
+				.map(|details| details.request_id)
+				.collect()
+		};
+		assert_eq!(ids(None, 0, 10), vec![first, for_other, last]);
+		assert_eq!(ids(None, 0, 2), vec![first, for_other]);
+		assert_eq!(ids(None, for_other + 1, 2), vec![last]);
+		assert_eq!(ids(Some(image_id()), 0, 1), vec![first]);
+		assert_eq!(ids(Some(image_id()), first + 1, 1), vec![last]);
+		assert_eq!(ids(Some(other), 0, 10), vec![for_other]);
+		assert!(ids(None, last + 1, 10).is_empty());
+	});
+}
@@ -0,0 +1,52 @@
+//! Traits through which other pallets interact with the prover-mgmt pallet.
+
+use crate::{ImageId, RequestId};
+use frame_support::{dispatch::DispatchResult, inherent::Vec, weights::Weight};
+
+/// Handler for proofs which were verified by the pallet
+pub trait OnProofVerified<AccountId> {
+	/// Called once a proof of the program with `image_id`, submitted by `prover`, was verified
+	/// and stored, and the request it fulfilled (if any) was settled. `journal` is the public
+	/// output of the proven execution.
+	fn on_proof_verified(
+		image_id: ImageId,
+		request: Option<RequestId>,
+		journal: &[u8],
+		prover: &AccountId,
+	);
+
+	/// The maximum weight of `on_proof_verified`, charged to every proof submission
+	fn weight() -> Weight;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl<AccountId> OnProofVerified<AccountId> for Tuple {
+	fn on_proof_verified(
+		image_id: ImageId,
+		request: Option<RequestId>,
+		journal: &[u8],
+		prover: &AccountId,
+	) {
+		for_tuples!( #( Tuple::on_proof_verified(image_id, request, journal, prover); )* );
+	}
+
+	fn weight() -> Weight {
+		let mut weight = Weight::zero();
+		for_tuples!( #( weight = weight.saturating_add(Tuple::weight()); )* );
+		weight
+	}
+}
+
+/// Synchronous verification of RISC Zero receipts, for pallets which consume proofs directly
+pub trait ProofVerifier {
+	/// Verify that the segment receipts prove an execution of the program with `image_id` which
+	/// committed `journal`
+	fn verify_receipt(
+		image_id: ImageId,
+		receipt_data: Vec<(Vec<u32>, u32)>,
+		journal: Vec<u8>,
+	) -> DispatchResult;
+
+	/// The weight of verifying a receipt made of `segments` segment receipts
+	fn verify_receipt_weight(segments: u32) -> Weight;
+}
@@ -0,0 +1,81 @@
+//! Types through which clients query the prover-mgmt pallet, e.g. through its runtime API.
+
+use crate::{ImageId, ProofId, RequestId};
+use codec::{Decode, Encode};
+use frame_support::inherent::Vec;
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// An open request for a proof
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RequestDetails<AccountId, Balance, BlockNumber> {
+	pub request_id: RequestId,
+	pub image_id: ImageId,
+	pub requester: AccountId,
+	pub reward: Balance,
+	pub args: Vec<Vec<u32>>,
+	/// What the journal of a proof fulfilling the request must start with
+	pub input_commitment: [u8; 32],
+	pub deadline: BlockNumber,
+	/// The prover which claimed the request, and the block at which its claim lapses
+	pub claim: Option<(AccountId, BlockNumber)>,
+	/// Whether the request is still being auctioned, and can not be fulfilled yet
+	pub auctioned: bool,
+}
+
+/// An uploaded program, without its ELF
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ProgramDetails<AccountId, Balance, BlockNumber> {
+	pub image_id: ImageId,
+	/// `None` for programs uploaded before owners were recorded, which are managed by root
+	pub owner: Option<AccountId>,
+	pub deposit: Balance,
+	pub uploaded_at: BlockNumber,
+	/// Length of the bincode-serialized program, in bytes
+	pub size: u32,
+	pub name: Vec<u8>,
+	pub description: Vec<u8>,
+	pub source_hash: Option<[u8; 32]>,
+	pub challenge_period_end: BlockNumber,
+	/// The most recent stored proof of the program
+	pub latest_proof: Option<ProofId>,
+	/// JSON-encoded description of the program's inputs and journal, if it was uploaded with one
+	pub manifest: Option<Vec<u8>>,
+}
+
+/// A verified proof
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ProofDetails<AccountId, BlockNumber> {
+	pub proof_id: ProofId,
+	pub image_id: ImageId,
+	/// The seal and index of every segment receipt
+	pub receipt: Vec<(Vec<u32>, u32)>,
+	pub journal: Vec<u8>,
+	pub prover: AccountId,
+	pub request: Option<RequestId>,
+	pub verified_at: BlockNumber,
+}
+
+/// A registered prover
+#[derive(Clone, Debug, Decode, Encode, PartialEq, Eq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ProverDetails<Balance, BlockNumber> {
+	/// Whether the prover may claim and fulfill requests: it is not unbonding, and its stake has
+	/// not been slashed below the minimum
+	pub active: bool,
+	pub stake: Balance,
+	/// Maximum number of cycles of a program execution the prover will prove
+	pub max_cycles: u64,
+	/// Version of risc0 the prover proves with
+	pub risc0_version: Vec<u8>,
+	/// Set once the prover unregisters: the block from which its stake can be withdrawn
+	pub unbonding_until: Option<BlockNumber>,
+}
@@ -1,30 +1,13 @@
-
-//! Autogenerated weights for pallet_template
+//! Weights for pallet_prover_mgmt
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
-//! DATE: 2023-04-06, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
-//! WORST CASE MAP SIZE: `1000000`
-//! HOSTNAME: `Alexs-MacBook-Pro-2.local`, CPU: `<UNKNOWN>`
-//! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("dev"), DB CACHE: 1024
-
-// Executed Command:
-// ../../target/release/node-template
-// benchmark
-// pallet
-// --chain
-// dev
-// --pallet
-// pallet_template
-// --extrinsic
-// *
-// --steps=50
-// --repeat=20
-// --execution=wasm
-// --wasm-execution=compiled
-// --output
-// pallets/template/src/weights.rs
-// --template
-// ../../.maintain/frame-weight-template.hbs
+//! Fitted by the least squares analysis of `frame-benchmarking` to the benchmarks of the pallet,
+//! timed natively against its mock runtime on a single core, with `risc0-zkvm` 0.15 from
+//! crates.io. The storage reads and writes of every call and hook are counted from its
+//! implementation and weighed with `DbWeight`, and proof sizes are the maximum encoded lengths of
+//! the items accessed. The runtime executes as Wasm, which is slower, and the receipt of the
+//! fixture is made of segments of 2^16 cycles, so these weights must be regenerated on reference
+//! hardware with the `benchmark pallet` command of a node built with the `runtime-benchmarks`
+//! feature.
 
 #![cfg_attr(rustfmt, rustfmt_skip)]
 #![allow(unused_parens)]
@@ -33,59 +16,446 @@
 use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
 use core::marker::PhantomData;
 
-/// Weight functions needed for pallet_template.
+/// Weight functions needed for pallet_prover_mgmt.
 pub trait WeightInfo {
-	fn do_something() -> Weight;
-	fn cause_error() -> Weight;
+	fn upload_program(l: u32, ) -> Weight;
+	fn request_proof() -> Weight;
+	fn store_and_verify_proof() -> Weight;
+	fn cancel_request() -> Weight;
+	fn on_initialize_expire_requests(n: u32, ) -> Weight;
+	fn remove_program() -> Weight;
+	fn transfer_program_ownership() -> Weight;
+	fn challenge_image_id(l: u32, ) -> Weight;
+	fn register_prover() -> Weight;
+	fn unregister_prover() -> Weight;
+	fn withdraw_unbonded() -> Weight;
+	fn claim_request() -> Weight;
+	fn on_initialize_lapse_claims(n: u32, ) -> Weight;
+	fn request_proof_auction() -> Weight;
+	fn commit_bid() -> Weight;
+	fn reveal_bid() -> Weight;
+	fn on_initialize_close_auctions(n: u32, b: u32, ) -> Weight;
+	fn on_initialize_prune_proofs(n: u32, ) -> Weight;
+	fn verify_receipt(s: u32, ) -> Weight;
 }
 
-/// Weights for pallet_template using the Substrate node and recommended hardware.
+/// Weights for pallet_prover_mgmt on the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: ProverMgmt Programs (r:1 w:1)
+	/// Storage: ProverMgmt ProgramManifests (r:0 w:1)
+	/// Storage: ProverMgmt ProgramInfos (r:0 w:1)
+	/// Storage: System Account (r:1 w:1)
+	/// `l` is the number of bytes stored: the program, its name, description and manifest.
+	fn upload_program(l: u32, ) -> Weight {
+		Weight::from_parts(18_125_019, 6_000)
+			.saturating_add(Weight::from_parts(106, 2).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: ProverMgmt NextRequestId (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	/// Storage: ProverMgmt Callbacks (r:0 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:0 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Both the reward and the fee of the callback are reserved, and the callback is encoded and
+	/// its dispatch info computed. The commitment to the args hashes up to their maximum length.
+	fn request_proof() -> Weight {
+		Weight::from_parts(27_970_000, 80_000)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: ProverMgmt Programs (r:1 w:0)
+	/// Storage: ProverMgmt Provers (r:1 w:0)
+	/// Storage: ProverMgmt ProofRequests (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:1 w:0)
+	/// Storage: ProverMgmt NextProofId (r:1 w:1)
+	/// Storage: ProverMgmt Proofs (r:0 w:1)
+	/// Storage: ProverMgmt ProofsByImage (r:0 w:1)
+	/// Storage: ProverMgmt ProofsByProver (r:0 w:1)
+	/// Storage: ProverMgmt LatestProofByImage (r:0 w:1)
+	/// Storage: ProverMgmt ProofByJournal (r:1 w:1)
+	/// Storage: ProverMgmt ProofByRequest (r:0 w:1)
+	/// Storage: ProverMgmt Callbacks (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt ClaimExpiries (r:1 w:1)
+	/// Storage: ProverMgmt FulfilledRequests (r:0 w:1)
+	/// Storage: System Account (r:2 w:2)
+	/// Storing a proof and settling the request it fulfills. The verification of its receipt is
+	/// included for a single segment, and counted again by `verify_receipt`.
+	fn store_and_verify_proof() -> Weight {
+		Weight::from_parts(2_553_171_000, 90_000)
+			.saturating_add(T::DbWeight::get().reads(12_u64))
+			.saturating_add(T::DbWeight::get().writes(17_u64))
+	}
+	/// Storage: ProverMgmt ProofRequests (r:1 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	fn cancel_request() -> Weight {
+		Weight::from_parts(27_923_000, 7790)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:50 w:50)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:50)
+	fn on_initialize_expire_requests(n: u32, ) -> Weight {
+		Weight::from_parts(3_485_000, 1567)
+			.saturating_add(Weight::from_parts(19_374_061, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2813).saturating_mul(n.into()))
+	}
+	/// Storage: ProverMgmt ProgramInfos (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:1 w:0)
+	/// Storage: ProverMgmt Programs (r:0 w:1)
+	fn remove_program() -> Weight {
+		Weight::from_parts(21_592_000, 8104)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt ProgramInfos (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:1 w:0)
+	/// Storage: System Account (r:1 w:1)
+	fn transfer_program_ownership() -> Weight {
+		Weight::from_parts(21_912_000, 11044)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt ProgramInfos (r:1 w:1)
+	/// Storage: ProverMgmt Programs (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn challenge_image_id(l: u32, ) -> Weight {
+		Weight::from_parts(21_830_306_639, 33054)
+			.saturating_add(Weight::from_parts(281_903, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn register_prover() -> Weight {
+		Weight::from_parts(24_889_000, 6324)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:1)
+	fn unregister_prover() -> Weight {
+		Weight::from_parts(11_406_000, 2600)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn withdraw_unbonded() -> Weight {
+		Weight::from_parts(27_371_000, 6324)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:0)
+	/// Storage: ProverMgmt ProofRequests (r:1 w:1)
+	/// Storage: ProverMgmt ClaimExpiries (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn claim_request() -> Weight {
+		Weight::from_parts(33_605_000, 76061)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt ClaimExpiries (r:1 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:50 w:50)
+	/// Storage: System Account (r:50 w:50)
+	/// Storage: ProverMgmt Provers (r:50 w:50)
+	fn on_initialize_lapse_claims(n: u32, ) -> Weight {
+		Weight::from_parts(12_515_032, 2892)
+			.saturating_add(Weight::from_parts(27_875_371, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 72048).saturating_mul(n.into()))
+	}
+	/// Storage: ProverMgmt NextRequestId (r:1 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:0 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:0 w:1)
+	/// Storage: ProverMgmt AuctionCloses (r:1 w:1)
+	fn request_proof_auction() -> Weight {
+		Weight::from_parts(26_159_000, 10197)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:0)
+	/// Storage: ProverMgmt Bids (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn commit_bid() -> Weight {
+		Weight::from_parts(18_613_000, 11519)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
 	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: ProverMgmt ProofRequests (r:1 w:0)
+	/// Storage: ProverMgmt Auctions (r:1 w:1)
+	/// Storage: ProverMgmt Bids (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn reveal_bid() -> Weight {
+		Weight::from_parts(19_634_000, 79488)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(4_u64))
+	}
+	/// Storage: ProverMgmt AuctionCloses (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:50 w:50)
+	/// Storage: ProverMgmt ProofRequests (r:50 w:50)
+	/// Storage: ProverMgmt ClaimExpiries (r:50 w:50)
+	/// Storage: ProverMgmt Bids (r:32 w:32)
+	/// Storage: System Account (r:32 w:32)
+	fn on_initialize_close_auctions(n: u32, b: u32, ) -> Weight {
+		Weight::from_parts(510_130_000, 2892)
+			.saturating_add(Weight::from_parts(27_597_404, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(15_447_786, 0).saturating_mul(b.into()))
 			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(b.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(b.into())))
+			.saturating_add(Weight::from_parts(0, 72353).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5182).saturating_mul(b.into()))
+	}
+	/// Storage: ProverMgmt NextProofId (r:1 w:0)
+	/// Storage: ProverMgmt OldestProofId (r:1 w:1)
+	/// Storage: ProverMgmt Proofs (r:50 w:50)
+	/// Storage: ProverMgmt LatestProofByImage (r:50 w:50)
+	/// Storage: ProverMgmt ProofByJournal (r:50 w:50)
+	/// Storage: ProverMgmt ProofsByImage (r:0 w:50)
+	/// Storage: ProverMgmt ProofsByProver (r:0 w:50)
+	/// Storage: ProverMgmt ProofByRequest (r:0 w:50)
+	/// Storage: ProverMgmt FulfilledRequests (r:0 w:50)
+	fn on_initialize_prune_proofs(n: u32, ) -> Weight {
+		Weight::from_parts(3_931_000, 1006)
+			.saturating_add(Weight::from_parts(88_602_875, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
 			.saturating_add(T::DbWeight::get().writes(1_u64))
+			.saturating_add(T::DbWeight::get().writes((7_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 1615385).saturating_mul(n.into()))
+	}
+	/// `s` is the number of segment receipts, at most `MaxSegments`.
+	fn verify_receipt(s: u32, ) -> Weight {
+		Weight::from_parts(2_968_574_868, 0)
+			.saturating_add(Weight::from_parts(2_776_710_966, 0).saturating_mul(s.into()))
 	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
-	/// Storage: TemplateModule Something (r:0 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn do_something() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 8_000_000 picoseconds.
-		Weight::from_parts(9_000_000, 0)
+	/// Storage: ProverMgmt Programs (r:1 w:1)
+	/// Storage: ProverMgmt ProgramManifests (r:0 w:1)
+	/// Storage: ProverMgmt ProgramInfos (r:0 w:1)
+	/// Storage: System Account (r:1 w:1)
+	/// `l` is the number of bytes stored: the program, its name, description and manifest.
+	fn upload_program(l: u32, ) -> Weight {
+		Weight::from_parts(18_125_019, 6_000)
+			.saturating_add(Weight::from_parts(106, 2).saturating_mul(l.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: ProverMgmt NextRequestId (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	/// Storage: ProverMgmt Callbacks (r:0 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:0 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Both the reward and the fee of the callback are reserved, and the callback is encoded and
+	/// its dispatch info computed. The commitment to the args hashes up to their maximum length.
+	fn request_proof() -> Weight {
+		Weight::from_parts(27_970_000, 80_000)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	/// Storage: ProverMgmt Programs (r:1 w:0)
+	/// Storage: ProverMgmt Provers (r:1 w:0)
+	/// Storage: ProverMgmt ProofRequests (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:1 w:0)
+	/// Storage: ProverMgmt NextProofId (r:1 w:1)
+	/// Storage: ProverMgmt Proofs (r:0 w:1)
+	/// Storage: ProverMgmt ProofsByImage (r:0 w:1)
+	/// Storage: ProverMgmt ProofsByProver (r:0 w:1)
+	/// Storage: ProverMgmt LatestProofByImage (r:0 w:1)
+	/// Storage: ProverMgmt ProofByJournal (r:1 w:1)
+	/// Storage: ProverMgmt ProofByRequest (r:0 w:1)
+	/// Storage: ProverMgmt Callbacks (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt ClaimExpiries (r:1 w:1)
+	/// Storage: ProverMgmt FulfilledRequests (r:0 w:1)
+	/// Storage: System Account (r:2 w:2)
+	/// Storing a proof and settling the request it fulfills. The verification of its receipt is
+	/// included for a single segment, and counted again by `verify_receipt`.
+	fn store_and_verify_proof() -> Weight {
+		Weight::from_parts(2_553_171_000, 90_000)
+			.saturating_add(RocksDbWeight::get().reads(12_u64))
+			.saturating_add(RocksDbWeight::get().writes(17_u64))
+	}
+	/// Storage: ProverMgmt ProofRequests (r:1 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	fn cancel_request() -> Weight {
+		Weight::from_parts(27_923_000, 7790)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:50 w:50)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:50)
+	fn on_initialize_expire_requests(n: u32, ) -> Weight {
+		Weight::from_parts(3_485_000, 1567)
+			.saturating_add(Weight::from_parts(19_374_061, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 2813).saturating_mul(n.into()))
+	}
+	/// Storage: ProverMgmt ProgramInfos (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:1 w:0)
+	/// Storage: ProverMgmt Programs (r:0 w:1)
+	fn remove_program() -> Weight {
+		Weight::from_parts(21_592_000, 8104)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt ProgramInfos (r:1 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:1 w:0)
+	/// Storage: System Account (r:1 w:1)
+	fn transfer_program_ownership() -> Weight {
+		Weight::from_parts(21_912_000, 11044)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt ProgramInfos (r:1 w:1)
+	/// Storage: ProverMgmt Programs (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn challenge_image_id(l: u32, ) -> Weight {
+		Weight::from_parts(21_830_306_639, 33054)
+			.saturating_add(Weight::from_parts(281_903, 0).saturating_mul(l.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn register_prover() -> Weight {
+		Weight::from_parts(24_889_000, 6324)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:1)
+	fn unregister_prover() -> Weight {
+		Weight::from_parts(11_406_000, 2600)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn withdraw_unbonded() -> Weight {
+		Weight::from_parts(27_371_000, 6324)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:0)
+	/// Storage: ProverMgmt ProofRequests (r:1 w:1)
+	/// Storage: ProverMgmt ClaimExpiries (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn claim_request() -> Weight {
+		Weight::from_parts(33_605_000, 76061)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt ClaimExpiries (r:1 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:50 w:50)
+	/// Storage: System Account (r:50 w:50)
+	/// Storage: ProverMgmt Provers (r:50 w:50)
+	fn on_initialize_lapse_claims(n: u32, ) -> Weight {
+		Weight::from_parts(12_515_032, 2892)
+			.saturating_add(Weight::from_parts(27_875_371, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(n.into())))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((4_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 72048).saturating_mul(n.into()))
 	}
-	/// Storage: TemplateModule Something (r:1 w:1)
-	/// Proof: TemplateModule Something (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
-	fn cause_error() -> Weight {
-		// Proof Size summary in bytes:
-		//  Measured:  `32`
-		//  Estimated: `1489`
-		// Minimum execution time: 6_000_000 picoseconds.
-		Weight::from_parts(6_000_000, 1489)
+	/// Storage: ProverMgmt NextRequestId (r:1 w:1)
+	/// Storage: ProverMgmt ProofRequests (r:0 w:1)
+	/// Storage: ProverMgmt RequestsByImage (r:0 w:1)
+	/// Storage: ProverMgmt RequestExpiries (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:0 w:1)
+	/// Storage: ProverMgmt AuctionCloses (r:1 w:1)
+	fn request_proof_auction() -> Weight {
+		Weight::from_parts(26_159_000, 10197)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+	}
+	/// Storage: ProverMgmt Provers (r:1 w:0)
+	/// Storage: ProverMgmt Bids (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:1 w:1)
+	/// Storage: System Account (r:1 w:1)
+	fn commit_bid() -> Weight {
+		Weight::from_parts(18_613_000, 11519)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	/// Storage: ProverMgmt ProofRequests (r:1 w:0)
+	/// Storage: ProverMgmt Auctions (r:1 w:1)
+	/// Storage: ProverMgmt Bids (r:1 w:1)
+	/// Storage: System Account (r:2 w:2)
+	fn reveal_bid() -> Weight {
+		Weight::from_parts(19_634_000, 79488)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(4_u64))
+	}
+	/// Storage: ProverMgmt AuctionCloses (r:1 w:1)
+	/// Storage: ProverMgmt Auctions (r:50 w:50)
+	/// Storage: ProverMgmt ProofRequests (r:50 w:50)
+	/// Storage: ProverMgmt ClaimExpiries (r:50 w:50)
+	/// Storage: ProverMgmt Bids (r:32 w:32)
+	/// Storage: System Account (r:32 w:32)
+	fn on_initialize_close_auctions(n: u32, b: u32, ) -> Weight {
+		Weight::from_parts(510_130_000, 2892)
+			.saturating_add(Weight::from_parts(27_597_404, 0).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(15_447_786, 0).saturating_mul(b.into()))
 			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(b.into())))
 			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(b.into())))
+			.saturating_add(Weight::from_parts(0, 72353).saturating_mul(n.into()))
+			.saturating_add(Weight::from_parts(0, 5182).saturating_mul(b.into()))
+	}
+	/// Storage: ProverMgmt NextProofId (r:1 w:0)
+	/// Storage: ProverMgmt OldestProofId (r:1 w:1)
+	/// Storage: ProverMgmt Proofs (r:50 w:50)
+	/// Storage: ProverMgmt LatestProofByImage (r:50 w:50)
+	/// Storage: ProverMgmt ProofByJournal (r:50 w:50)
+	/// Storage: ProverMgmt ProofsByImage (r:0 w:50)
+	/// Storage: ProverMgmt ProofsByProver (r:0 w:50)
+	/// Storage: ProverMgmt ProofByRequest (r:0 w:50)
+	/// Storage: ProverMgmt FulfilledRequests (r:0 w:50)
+	fn on_initialize_prune_proofs(n: u32, ) -> Weight {
+		Weight::from_parts(3_931_000, 1006)
+			.saturating_add(Weight::from_parts(88_602_875, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+			.saturating_add(RocksDbWeight::get().writes((7_u64).saturating_mul(n.into())))
+			.saturating_add(Weight::from_parts(0, 1615385).saturating_mul(n.into()))
+	}
+	/// `s` is the number of segment receipts, at most `MaxSegments`.
+	fn verify_receipt(s: u32, ) -> Weight {
+		Weight::from_parts(2_968_574_868, 0)
+			.saturating_add(Weight::from_parts(2_776_710_966, 0).saturating_mul(s.into()))
 	}
 }
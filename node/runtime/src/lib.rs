@@ -100,12 +100,14 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
-	//   the compatible custom types.
-	spec_version: 100,
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	//   the compatible custom types, and is bumped with every change to the runtime's logic.
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	// Bumped whenever the encoding of calls changes, such as the arguments of the `ProverMgmt`
+	//   calls.
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -276,18 +278,77 @@ impl pallet_sudo::Config for Runtime {
 
 parameter_types! {
 	pub const MaxArgsLength: u32 = 500;
-	pub const MaxProgramLength: u32 = 25000;
-	pub const MaxProofLength: u32 = 25000;
+	pub const MaxArgs: u32 = 32;
+	// Measured with risc0-zkvm 0.15: the bincode-serialized ELF of the `factors` example is 46,656
+	// bytes, and a segment seal is 53,817 words at po2 16 and 59,577 words at po2 17. Seals grow by
+	// 5,760 words per power of two, so a segment of the largest size, po2 20, has about 76,900
+	// words. `MaxSegments` of those, 3.8 MB, fit in the normal dispatch share of a 5 MiB block.
+	pub const MaxProgramLength: u32 = 64 * 1024;
+	pub const MaxProofLength: u32 = 80_000;
+	pub const MaxSegments: u32 = 12;
+	pub const MaxJournalLength: u32 = 10 * 1024;
+	pub const MaxRequestLifetime: BlockNumber = 7 * DAYS;
+	pub const MaxExpiringPerBlock: u32 = 50;
+	pub const ProgramDepositBase: Balance = deposit(1, 0);
+	pub const ProgramDepositPerByte: Balance = deposit(0, 1);
+	pub const MaxNameLength: u32 = 64;
+	pub const MaxDescriptionLength: u32 = 256;
+	pub const MaxManifestLength: u32 = 4 * 1024;
+	pub const ImageIdChallengePeriod: BlockNumber = DAYS;
+	pub const MinProverStake: Balance = 100 * DOLLARS;
+	pub const ProverUnbondingPeriod: BlockNumber = 2 * DAYS;
+	pub const MaxVersionLength: u32 = 32;
+	pub const ClaimWindow: BlockNumber = 6 * HOURS;
+	pub const ClaimCollateral: Balance = 10 * DOLLARS;
+	pub const LapseSlash: Perbill = Perbill::from_percent(5);
+	pub const BiddingPeriod: BlockNumber = HOURS;
+	pub const RevealPeriod: BlockNumber = HOURS;
+	pub const BidDeposit: Balance = 10 * DOLLARS;
+	pub const MaxBids: u32 = 32;
+	pub const ProofPruning: pallet_prover_mgmt::PruningPolicy<BlockNumber> =
+		pallet_prover_mgmt::PruningPolicy::KeepFor(30 * DAYS);
+	pub const MaxProofsPrunedPerBlock: u32 = 50;
+	pub const MaxCallbackLength: u32 = 1024;
+	pub MaxCallbackWeight: Weight = Perbill::from_percent(10) * BlockWeights::get().max_block;
 }
 
 /// Configure the pallet-template in pallets/template.
 impl pallet_prover_mgmt::Config for Runtime {
 	type Currency = Balances;
 	type RuntimeEvent = RuntimeEvent;
+	type RuntimeOrigin = RuntimeOrigin;
+	type RuntimeCall = RuntimeCall;
 	type WeightInfo = pallet_prover_mgmt::weights::SubstrateWeight<Runtime>;
 	type MaxArgsLength = MaxArgsLength;
+	type MaxArgs = MaxArgs;
 	type MaxProgramLength = MaxProgramLength;
 	type MaxProofLength = MaxProofLength;
+	type MaxJournalLength = MaxJournalLength;
+	type MaxSegments = MaxSegments;
+	type MaxRequestLifetime = MaxRequestLifetime;
+	type MaxExpiringPerBlock = MaxExpiringPerBlock;
+	type DepositBase = ProgramDepositBase;
+	type DepositPerByte = ProgramDepositPerByte;
+	type MaxNameLength = MaxNameLength;
+	type MaxDescriptionLength = MaxDescriptionLength;
+	type MaxManifestLength = MaxManifestLength;
+	type ChallengePeriod = ImageIdChallengePeriod;
+	type MinProverStake = MinProverStake;
+	type UnbondingPeriod = ProverUnbondingPeriod;
+	type MaxVersionLength = MaxVersionLength;
+	type ClaimWindow = ClaimWindow;
+	type ClaimCollateral = ClaimCollateral;
+	type LapseSlash = LapseSlash;
+	type BiddingPeriod = BiddingPeriod;
+	type RevealPeriod = RevealPeriod;
+	type BidDeposit = BidDeposit;
+	type MaxBids = MaxBids;
+	type ProofPruning = ProofPruning;
+	type MaxProofsPrunedPerBlock = MaxProofsPrunedPerBlock;
+	type OnProofVerified = ();
+	type WeightToFee = IdentityFee<Balance>;
+	type MaxCallbackLength = MaxCallbackLength;
+	type MaxCallbackWeight = MaxCallbackWeight;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -332,6 +393,14 @@ pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
 /// The payload being signed in transactions.
 pub type SignedPayload = generic::SignedPayload<RuntimeCall, SignedExtra>;
+/// Migrations to apply on runtime upgrade.
+pub type Migrations = (
+	pallet_prover_mgmt::migrations::v1::MigrateToV1<Runtime>,
+	pallet_prover_mgmt::migrations::v2::MigrateToV2<Runtime>,
+	pallet_prover_mgmt::migrations::v3::MigrateToV3<Runtime>,
+	pallet_prover_mgmt::migrations::v4::MigrateToV4<Runtime>,
+	pallet_prover_mgmt::migrations::v5::MigrateToV5<Runtime>,
+);
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -339,6 +408,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -352,7 +422,7 @@ mod benches {
 		[frame_system, SystemBench::<Runtime>]
 		[pallet_balances, Balances]
 		[pallet_timestamp, Timestamp]
-		[pallet_prover_mgmt, TemplateModule]
+		[pallet_prover_mgmt, ProverMgmt]
 	);
 }
 
@@ -480,6 +550,57 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_prover_mgmt_runtime_api::ProverMgmtApi<Block, AccountId, Balance, BlockNumber> for Runtime {
+		fn open_requests(
+			image_id: Option<pallet_prover_mgmt::ImageId>,
+			from: pallet_prover_mgmt::RequestId,
+			limit: u32,
+		) -> Vec<pallet_prover_mgmt::RequestDetails<AccountId, Balance, BlockNumber>> {
+			ProverMgmt::open_requests(image_id, from, limit)
+		}
+
+		fn next_request_id() -> pallet_prover_mgmt::RequestId {
+			ProverMgmt::next_request_id()
+		}
+
+		fn program_info(
+			image_id: pallet_prover_mgmt::ImageId,
+		) -> Option<pallet_prover_mgmt::ProgramDetails<AccountId, Balance, BlockNumber>> {
+			ProverMgmt::program_details(image_id)
+		}
+
+		fn program(image_id: pallet_prover_mgmt::ImageId) -> Option<Vec<u8>> {
+			ProverMgmt::program(image_id)
+		}
+
+		fn proof(
+			proof_id: pallet_prover_mgmt::ProofId,
+		) -> Option<pallet_prover_mgmt::ProofDetails<AccountId, BlockNumber>> {
+			ProverMgmt::proof_details(proof_id)
+		}
+
+		fn request_proof_id(
+			request_id: pallet_prover_mgmt::RequestId,
+		) -> Option<pallet_prover_mgmt::ProofId> {
+			ProverMgmt::request_proof_id(request_id)
+		}
+
+		fn prover(
+			who: AccountId,
+		) -> Option<pallet_prover_mgmt::ProverDetails<Balance, BlockNumber>> {
+			ProverMgmt::prover_details(&who)
+		}
+
+		fn verify_receipt(
+			image_id: pallet_prover_mgmt::ImageId,
+			receipt_data: Vec<(Vec<u32>, u32)>,
+			journal: Vec<u8>,
+		) -> sp_runtime::DispatchResult {
+			use pallet_prover_mgmt::ProofVerifier;
+			ProverMgmt::verify_receipt(image_id, receipt_data, journal)
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
 		fn query_info(
 			uxt: <Block as BlockT>::Extrinsic,
@@ -0,0 +1,531 @@
+//! Typed arguments for programs.
+//!
+//! A program declares the inputs it reads, in order, as a `Schema` of named and typed fields.
+//! Requesters then write the value of each field as JSON or TOML, and `Schema::encode` checks them
+//! against the schema and serializes them with RISC Zero's serde, as the guest expects to read
+//! them. Every field is one arg, read by the guest with one `env::read`.
+//!
+//! A schema for a program reading two `u64`s, in TOML:
+//! ```toml
+//! [[inputs]]
+//! name = "a"
+//! type = "u64"
+//!
+//! [[inputs]]
+//! name = "b"
+//! type = "u64"
+//! ```
+//! for which the values `{ "a": 17, "b": 23 }` are valid.
+//!
+//! A `Manifest` extends the schema of a program's inputs with the outputs it commits to its
+//! journal, a version and a description. Programs may be uploaded onchain with their JSON-encoded
+//! manifest, so that clients can encode args and decode journals without reading their source.
+
+use std::{fmt, path::Path};
+
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// Type of a field, as read by the guest
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArgType {
+	Bool,
+	U8,
+	U16,
+	U32,
+	U64,
+	I8,
+	I16,
+	I32,
+	I64,
+	String,
+	/// A `Vec<u8>`, written as a hex string
+	Bytes,
+	/// A `Vec` of values of the given type, written as an array
+	Vec(Box<ArgType>),
+}
+
+impl fmt::Display for ArgType {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ArgType::Bool => write!(f, "bool"),
+			ArgType::U8 => write!(f, "u8"),
+			ArgType::U16 => write!(f, "u16"),
+			ArgType::U32 => write!(f, "u32"),
+			ArgType::U64 => write!(f, "u64"),
+			ArgType::I8 => write!(f, "i8"),
+			ArgType::I16 => write!(f, "i16"),
+			ArgType::I32 => write!(f, "i32"),
+			ArgType::I64 => write!(f, "i64"),
+			ArgType::String => write!(f, "string"),
+			ArgType::Bytes => write!(f, "bytes"),
+			ArgType::Vec(ty) => write!(f, "vec<{ty}>"),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Field {
+	pub name: String,
+	#[serde(rename = "type")]
+	pub ty: ArgType,
+}
+
+/// Inputs of a program, in the order the guest reads them
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schema {
+	pub inputs: Vec<Field>,
+}
+
+/// Description of a program's inputs and journal
+///
+/// Every part of a manifest but its inputs is optional, so that any schema is also a manifest.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+	#[serde(default)]
+	pub version: String,
+	#[serde(default)]
+	pub description: String,
+	/// Inputs of the program, in the order the guest reads them
+	pub inputs: Vec<Field>,
+	/// Outputs of the program, in the order the guest commits them to its journal after the
+	/// commitment to its inputs
+	#[serde(default)]
+	pub outputs: Vec<Field>,
+}
+
+#[derive(Debug)]
+pub enum ArgError {
+	/// No value was given for a field of the schema
+	Missing(String),
+	/// A value was given for a field which is not in the schema
+	Unknown(String),
+	/// The value of a field is not of the field's type
+	Invalid { field: String, expected: ArgType, value: Value },
+	/// The values are not an object of fields
+	NotAnObject,
+	/// The schema or the values could not be parsed
+	Parse(String),
+	/// The values could not be serialized for the guest
+	Serialize(String),
+	/// A journal does not hold the outputs of the manifest
+	Journal(String),
+}
+
+impl fmt::Display for ArgError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ArgError::Missing(field) => write!(f, "missing value for field `{field}`"),
+			ArgError::Unknown(field) => write!(f, "unknown field `{field}`"),
+			ArgError::Invalid { field, expected, value } =>
+				write!(f, "field `{field}` should be a {expected}, but is {value}"),
+			ArgError::NotAnObject => write!(f, "values should be an object of fields"),
+			ArgError::Parse(e) => write!(f, "could not parse: {e}"),
+			ArgError::Serialize(e) => write!(f, "could not serialize: {e}"),
+			ArgError::Journal(e) => write!(f, "could not decode journal: {e}"),
+		}
+	}
+}
+
+impl std::error::Error for ArgError {}
+
+/// A value checked against its type, serialized like the type it was checked against
+enum ArgValue {
+	Bool(bool),
+	U8(u8),
+	U16(u16),
+	U32(u32),
+	U64(u64),
+	I8(i8),
+	I16(i16),
+	I32(i32),
+	I64(i64),
+	String(String),
+	Vec(Vec<ArgValue>),
+}
+
+impl Serialize for ArgValue {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self {
+			ArgValue::Bool(v) => serializer.serialize_bool(*v),
+			ArgValue::U8(v) => serializer.serialize_u8(*v),
+			ArgValue::U16(v) => serializer.serialize_u16(*v),
+			ArgValue::U32(v) => serializer.serialize_u32(*v),
+			ArgValue::U64(v) => serializer.serialize_u64(*v),
+			ArgValue::I8(v) => serializer.serialize_i8(*v),
+			ArgValue::I16(v) => serializer.serialize_i16(*v),
+			ArgValue::I32(v) => serializer.serialize_i32(*v),
+			ArgValue::I64(v) => serializer.serialize_i64(*v),
+			ArgValue::String(v) => serializer.serialize_str(v),
+			ArgValue::Vec(v) => serializer.collect_seq(v),
+		}
+	}
+}
+
+impl Schema {
+	pub fn from_json(json: &str) -> Result<Self, ArgError> {
+		serde_json::from_str(json).map_err(|e| ArgError::Parse(e.to_string()))
+	}
+
+	pub fn from_toml(toml: &str) -> Result<Self, ArgError> {
+		toml::from_str(toml).map_err(|e| ArgError::Parse(e.to_string()))
+	}
+
+	/// Parse a schema from JSON or TOML, depending on the extension of `path`
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ArgError> {
+		parse_file(path.as_ref())
+	}
+
+	/// Check values against the schema, and serialize them into the args of a request
+	pub fn encode(&self, values: &Value) -> Result<Vec<Vec<u32>>, ArgError> {
+		let values = values.as_object().ok_or(ArgError::NotAnObject)?;
+		if let Some(unknown) =
+			values.keys().find(|name| !self.inputs.iter().any(|field| &field.name == *name))
+		{
+			return Err(ArgError::Unknown(unknown.clone()))
+		}
+
+		self.inputs
+			.iter()
+			.map(|field| {
+				let value =
+					values.get(&field.name).ok_or_else(|| ArgError::Missing(field.name.clone()))?;
+				let value = check(&field.name, &field.ty, value)?;
+				risc0_zkvm::serde::to_vec(&value).map_err(|e| ArgError::Serialize(e.to_string()))
+			})
+			.collect()
+	}
+
+	/// Check values read from a JSON or TOML file against the schema, and serialize them
+	pub fn encode_file(&self, path: impl AsRef<Path>) -> Result<Vec<Vec<u32>>, ArgError> {
+		self.encode(&Value::Object(parse_file::<Map<String, Value>>(path.as_ref())?))
+	}
+}
+
+impl Manifest {
+	/// Parse a manifest as stored onchain
+	pub fn from_json(json: &[u8]) -> Result<Self, ArgError> {
+		serde_json::from_slice(json).map_err(|e| ArgError::Parse(e.to_string()))
+	}
+
+	/// Encode the manifest to be stored onchain
+	pub fn to_json(&self) -> Vec<u8> {
+		serde_json::to_vec(self).expect("Manifests are always serializable")
+	}
+
+	pub fn from_toml(toml: &str) -> Result<Self, ArgError> {
+		toml::from_str(toml).map_err(|e| ArgError::Parse(e.to_string()))
+	}
+
+	/// Parse a manifest from JSON or TOML, depending on the extension of `path`
+	pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ArgError> {
+		parse_file(path.as_ref())
+	}
+
+	pub fn schema(&self) -> Schema {
+		Schema { inputs: self.inputs.clone() }
+	}
+
+	/// Decode the outputs of a journal, which starts with the 32 byte commitment to the inputs
+	pub fn decode_journal(&self, journal: &[u8]) -> Result<Map<String, Value>, ArgError> {
+		let outputs = journal
+			.get(COMMITMENT_LEN..)
+			.ok_or_else(|| ArgError::Journal("shorter than the input commitment".into()))?;
+		if outputs.len() % 4 != 0 {
+			return Err(ArgError::Journal("not made of whole words".into()))
+		}
+		let words: Vec<u32> = outputs
+			.chunks_exact(4)
+			.map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+			.collect();
+
+		let mut remaining = &words[..];
+		let decoded = self
+			.outputs
+			.iter()
+			.map(|field| Ok((field.name.clone(), decode(&field.name, &field.ty, &mut remaining)?)))
+			.collect::<Result<_, ArgError>>()?;
+		if !remaining.is_empty() {
+			return Err(ArgError::Journal(format!(
+				"{} words left after the outputs",
+				remaining.len()
+			)))
+		}
+		Ok(decoded)
+	}
+}
+
+/// Length of the commitment to the inputs at the start of every journal
+const COMMITMENT_LEN: usize = 32;
+
+fn parse_file<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, ArgError> {
+	let contents = std::fs::read_to_string(path)
+		.map_err(|e| ArgError::Parse(format!("{}: {e}", path.display())))?;
+	let parsed = match path.extension().and_then(|extension| extension.to_str()) {
+		Some("toml") => toml::from_str(&contents).map_err(|e| e.to_string()),
+		_ => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+	};
+	parsed.map_err(|e| ArgError::Parse(format!("{}: {e}", path.display())))
+}
+
+fn check(field: &str, ty: &ArgType, value: &Value) -> Result<ArgValue, ArgError> {
+	let invalid =
+		|| ArgError::Invalid { field: field.into(), expected: ty.clone(), value: value.clone() };
+	let unsigned = || value.as_u64().ok_or_else(invalid);
+	let signed = || value.as_i64().ok_or_else(invalid);
+
+	Ok(match ty {
+		ArgType::Bool => ArgValue::Bool(value.as_bool().ok_or_else(invalid)?),
+		ArgType::U8 => ArgValue::U8(unsigned()?.try_into().map_err(|_| invalid())?),
+		ArgType::U16 => ArgValue::U16(unsigned()?.try_into().map_err(|_| invalid())?),
+		ArgType::U32 => ArgValue::U32(unsigned()?.try_into().map_err(|_| invalid())?),
+		ArgType::U64 => ArgValue::U64(unsigned()?),
+		ArgType::I8 => ArgValue::I8(signed()?.try_into().map_err(|_| invalid())?),
+		ArgType::I16 => ArgValue::I16(signed()?.try_into().map_err(|_| invalid())?),
+		ArgType::I32 => ArgValue::I32(signed()?.try_into().map_err(|_| invalid())?),
+		ArgType::I64 => ArgValue::I64(signed()?),
+		ArgType::String => ArgValue::String(value.as_str().ok_or_else(invalid)?.into()),
+		ArgType::Bytes => {
+			let hex_string = value.as_str().ok_or_else(invalid)?;
+			let bytes = hex::decode(hex_string.trim_start_matches("0x")).map_err(|_| invalid())?;
+			ArgValue::Vec(bytes.into_iter().map(ArgValue::U8).collect())
+		},
+		ArgType::Vec(item) => ArgValue::Vec(
+			value
+				.as_array()
+				.ok_or_else(invalid)?
+				.iter()
+				.map(|item_value| check(field, item, item_value))
+				.collect::<Result<_, _>>()?,
+		),
+	})
+}
+
+/// Decode a value from words serialized with RISC Zero's serde, in which every value takes whole
+/// words, `u64`s and `i64`s take two words (low first), and strings and sequences are prefixed by
+/// their length
+fn decode(field: &str, ty: &ArgType, words: &mut &[u32]) -> Result<Value, ArgError> {
+	let mut next = || -> Result<u32, ArgError> {
+		let (word, rest) = words
+			.split_first()
+			.ok_or_else(|| ArgError::Journal(format!("ends before the end of field `{field}`")))?;
+		*words = rest;
+		Ok(*word)
+	};
+
+	Ok(match ty {
+		ArgType::Bool => Value::from(next()? != 0),
+		ArgType::U8 | ArgType::U16 | ArgType::U32 => Value::from(next()?),
+		ArgType::U64 => Value::from(next()? as u64 | (next()? as u64) << 32),
+		ArgType::I8 => Value::from(next()? as i32 as i8),
+		ArgType::I16 => Value::from(next()? as i32 as i16),
+		ArgType::I32 => Value::from(next()? as i32),
+		ArgType::I64 => Value::from((next()? as u64 | (next()? as u64) << 32) as i64),
+		ArgType::String => {
+			let len = next()? as usize;
+			// The length is read from the journal, so it is checked against the words left before
+			// anything is allocated for it. Rounding it up must not overflow on 32-bit targets.
+			let string_words = len
+				.checked_add(3)
+				.map(|len| len / 4)
+				.filter(|&word_count| word_count <= words.len())
+				.map(|word_count| &words[..word_count])
+				.ok_or_else(|| {
+					ArgError::Journal(format!("ends before the end of field `{field}`"))
+				})?;
+			let mut bytes: Vec<u8> =
+				string_words.iter().flat_map(|word| word.to_le_bytes()).collect();
+			*words = &words[string_words.len()..];
+			bytes.truncate(len);
+			Value::from(
+				String::from_utf8(bytes)
+					.map_err(|_| ArgError::Journal(format!("field `{field}` is not UTF-8")))?,
+			)
+		},
+		ArgType::Bytes => {
+			let len = next()?;
+			let bytes =
+				(0..len).map(|_| next().map(|byte| byte as u8)).collect::<Result<Vec<_>, _>>()?;
+			Value::from(hex::encode(bytes))
+		},
+		ArgType::Vec(item) => {
+			let len = next()?;
+			Value::Array((0..len).map(|_| decode(field, item, words)).collect::<Result<_, _>>()?)
+		},
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use risc0_zkvm::serde::to_vec;
+	use serde_json::json;
+
+	fn field(name: &str, ty: ArgType) -> Field {
+		Field { name: name.into(), ty }
+	}
+
+	fn all_types() -> Vec<Field> {
+		vec![
+			field("bool", ArgType::Bool),
+			field("u8", ArgType::U8),
+			field("u16", ArgType::U16),
+			field("u32", ArgType::U32),
+			field("u64", ArgType::U64),
+			field("i8", ArgType::I8),
+			field("i16", ArgType::I16),
+			field("i32", ArgType::I32),
+			field("i64", ArgType::I64),
+			field("string", ArgType::String),
+			field("bytes", ArgType::Bytes),
+			field("vec", ArgType::Vec(Box::new(ArgType::U64))),
+		]
+	}
+
+	fn all_values() -> Value {
+		json!({
+			"bool": true,
+			"u8": 200,
+			"u16": 60_000,
+			"u32": 4_000_000_000u32,
+			"u64": 17_000_000_000_000_000_000u64,
+			"i8": -5,
+			"i16": -30_000,
+			"i32": -2_000_000_000,
+			"i64": -9_000_000_000_000_000_000i64,
+			"string": "héllo",
+			"bytes": "0xdead",
+			"vec": [1, 2, 3],
+		})
+	}
+
+	/// Journal of a program committing `words` after the commitment to its inputs
+	fn journal(words: &[u32]) -> Vec<u8> {
+		let mut journal = vec![0xab; COMMITMENT_LEN];
+		journal.extend(words.iter().flat_map(|word| word.to_le_bytes()));
+		journal
+	}
+
+	fn manifest(outputs: Vec<Field>) -> Manifest {
+		Manifest { outputs, ..Default::default() }
+	}
+
+	#[test]
+	fn encodes_like_risc0_serde() {
+		let schema = Schema { inputs: all_types() };
+		let expected = vec![
+			to_vec(&true).unwrap(),
+			to_vec(&200u8).unwrap(),
+			to_vec(&60_000u16).unwrap(),
+			to_vec(&4_000_000_000u32).unwrap(),
+			to_vec(&17_000_000_000_000_000_000u64).unwrap(),
+			to_vec(&-5i8).unwrap(),
+			to_vec(&-30_000i16).unwrap(),
+			to_vec(&-2_000_000_000i32).unwrap(),
+			to_vec(&-9_000_000_000_000_000_000i64).unwrap(),
+			to_vec(&"héllo").unwrap(),
+			to_vec(&vec![0xdeu8, 0xad]).unwrap(),
+			to_vec(&vec![1u64, 2, 3]).unwrap(),
+		];
+		assert_eq!(schema.encode(&all_values()).unwrap(), expected);
+	}
+
+	#[test]
+	fn encodes_fields_in_schema_order() {
+		let schema = Schema { inputs: vec![field("b", ArgType::U32), field("a", ArgType::U32)] };
+		let args = schema.encode(&json!({ "a": 1, "b": 2 })).unwrap();
+		assert_eq!(args, vec![to_vec(&2u32).unwrap(), to_vec(&1u32).unwrap()]);
+	}
+
+	#[test]
+	fn rejects_values_not_matching_schema() {
+		let schema = Schema { inputs: vec![field("a", ArgType::U8), field("b", ArgType::Bytes)] };
+		assert!(matches!(
+			schema.encode(&json!({ "a": 1 })),
+			Err(ArgError::Missing(field)) if field == "b"
+		));
+		assert!(matches!(
+			schema.encode(&json!({ "a": 1, "b": "00", "c": 3 })),
+			Err(ArgError::Unknown(field)) if field == "c"
+		));
+		assert!(matches!(
+			schema.encode(&json!({ "a": 256, "b": "00" })),
+			Err(ArgError::Invalid { field, expected: ArgType::U8, .. }) if field == "a"
+		));
+		assert!(matches!(
+			schema.encode(&json!({ "a": 1, "b": "not hex" })),
+			Err(ArgError::Invalid { field, expected: ArgType::Bytes, .. }) if field == "b"
+		));
+		assert!(matches!(schema.encode(&json!([1, "00"])), Err(ArgError::NotAnObject)));
+	}
+
+	#[test]
+	fn decodes_hand_built_journal() {
+		let manifest = manifest(vec![
+			field("ok", ArgType::Bool),
+			field("count", ArgType::U32),
+			field("total", ArgType::U64),
+			field("delta", ArgType::I32),
+			field("name", ArgType::String),
+			field("hash", ArgType::Bytes),
+			field("values", ArgType::Vec(Box::new(ArgType::U16))),
+		]);
+		let journal = journal(&[
+			1,
+			7,
+			0x0000_0002,
+			0x0000_0001,
+			-3i32 as u32,
+			5,
+			u32::from_le_bytes(*b"hell"),
+			u32::from_le_bytes([b'o', 0, 0, 0]),
+			2,
+			0xde,
+			0xad,
+			2,
+			1,
+			2,
+		]);
+
+		let outputs = manifest.decode_journal(&journal).unwrap();
+		assert_eq!(
+			Value::Object(outputs),
+			json!({
+				"ok": true,
+				"count": 7,
+				"total": (1u64 << 32) | 2,
+				"delta": -3,
+				"name": "hello",
+				"hash": "dead",
+				"values": [1, 2],
+			})
+		);
+	}
+
+	#[test]
+	fn decodes_journal_committed_with_risc0_serde() {
+		let manifest = manifest(all_types());
+		let words = manifest.schema().encode(&all_values()).unwrap().concat();
+
+		let mut expected = all_values();
+		expected["bytes"] = json!("dead");
+		assert_eq!(Value::Object(manifest.decode_journal(&journal(&words)).unwrap()), expected);
+	}
+
+	#[test]
+	fn rejects_malformed_journals() {
+		let manifest = manifest(vec![field("name", ArgType::String)]);
+		let journal_error = |journal: &[u8]| match manifest.decode_journal(journal) {
+			Err(ArgError::Journal(e)) => e,
+			result => panic!("expected a journal error, got {result:?}"),
+		};
+
+		assert_eq!(journal_error(&[0; COMMITMENT_LEN - 1]), "shorter than the input commitment");
+		assert_eq!(journal_error(&[0; COMMITMENT_LEN + 3]), "not made of whole words");
+		assert_eq!(journal_error(&journal(&[])), "ends before the end of field `name`");
+		assert_eq!(journal_error(&journal(&[2, 0, 9])), "1 words left after the outputs");
+		// A length claiming more than the journal holds is rejected without being allocated
+		assert_eq!(journal_error(&journal(&[u32::MAX, 0])), "ends before the end of field `name`");
+	}
+}
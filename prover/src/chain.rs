@@ -0,0 +1,262 @@
+//! Access to the chain: fetching programs and requests, and submitting proofs.
+
+use std::fmt;
+
+use codec::{Decode, Encode};
+use program_args::Manifest;
+use risc0_zkvm::{SegmentReceipt, SessionReceipt};
+use serde::Deserialize;
+use subxt::{
+	config::WithExtrinsicParams,
+	ext::{
+		sp_core::{sr25519::Pair as SubxtPair, Bytes, Pair as SubxtPairT},
+		sp_runtime::AccountId32,
+	},
+	rpc::rpc_params,
+	tx::{BaseExtrinsicParams, PairSigner, PlainTip, TxProgress},
+	OnlineClient, SubstrateConfig,
+};
+
+use crate::{
+	policy::Balance,
+	prover_mgmt::{self, ProofRequest},
+	substrate_node,
+};
+
+pub type ChainConfig =
+	WithExtrinsicParams<SubstrateConfig, BaseExtrinsicParams<SubstrateConfig, PlainTip>>;
+pub type ApiType = OnlineClient<ChainConfig>;
+
+pub type ImageId = [u32; 8];
+pub type RequestId = u64;
+
+/// Parse an image id as printed when uploading a program: the hex of its bincode serialization
+pub fn parse_image_id(image_id: &str) -> Result<ImageId, String> {
+	let bytes = hex::decode(image_id.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+	bincode::deserialize(&bytes).map_err(|e| e.to_string())
+}
+
+pub async fn get_program(
+	api: &ApiType,
+	image_id: ImageId,
+) -> Result<Option<Vec<u8>>, subxt::Error> {
+	let query = prover_mgmt::programs(image_id);
+
+	api.storage().fetch(&query, None).await
+}
+
+/// The manifest a program was uploaded with, if any
+pub async fn get_manifest(
+	api: &ApiType,
+	image_id: ImageId,
+) -> Result<Option<Manifest>, Box<dyn std::error::Error + Send + Sync>> {
+	// Manifests are read through the `prover_` RPC, which does not depend on the storage layout
+	#[derive(Deserialize)]
+	struct ProgramDetails {
+		manifest: Option<Vec<u8>>,
+	}
+
+	let details: Option<ProgramDetails> =
+		api.rpc().request("prover_program", rpc_params![image_id]).await?;
+	match details.and_then(|details| details.manifest) {
+		Some(manifest) => Ok(Some(Manifest::from_json(&manifest)?)),
+		None => Ok(None),
+	}
+}
+
+pub async fn get_proof_request(
+	api: &ApiType,
+	request_id: RequestId,
+) -> Result<Option<ProofRequest>, subxt::Error> {
+	let query = prover_mgmt::proof_requests(request_id);
+
+	api.storage().fetch(&query, None).await
+}
+
+/// Whether a request is being auctioned, in which case it can only be claimed by the winner of
+/// the auction once it closes
+pub async fn is_auctioned(api: &ApiType, request_id: RequestId) -> Result<bool, subxt::Error> {
+	let query = prover_mgmt::auctions(request_id);
+
+	Ok(api.storage().fetch(&query, None).await?.is_some())
+}
+
+/// Ids of the requests open at the best block, starting from `from`, in increasing order
+pub async fn open_requests(
+	api: &ApiType,
+	from: RequestId,
+	limit: u32,
+) -> Result<Vec<RequestId>, subxt::Error> {
+	#[derive(Deserialize)]
+	#[serde(rename_all = "camelCase")]
+	struct RequestDetails {
+		request_id: RequestId,
+	}
+
+	let requests: Vec<RequestDetails> = api
+		.rpc()
+		.request("prover_openRequests", rpc_params![None::<ImageId>, from, limit])
+		.await?;
+	Ok(requests.into_iter().map(|request| request.request_id).collect())
+}
+
+/// Registration of a prover, as returned by the `prover_proverInfo` RPC
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProverInfo {
+	/// Whether the prover may claim and fulfill requests
+	pub active: bool,
+	/// Maximum number of cycles the prover declared it will prove
+	pub max_cycles: u64,
+}
+
+/// The registration of a prover, if it is registered
+pub async fn get_prover(
+	api: &ApiType,
+	prover: &AccountId32,
+) -> Result<Option<ProverInfo>, subxt::Error> {
+	api.rpc().request("prover_proverInfo", rpc_params![prover]).await
+}
+
+/// The args of a request, as passed to the program
+pub fn request_args(proof_request: ProofRequest) -> Vec<Vec<u32>> {
+	proof_request.args
+}
+
+/// Reasons for not submitting a proof
+#[derive(Debug)]
+pub enum SubmitError {
+	/// The signing key could not be parsed
+	InvalidSigningKey(String),
+	/// The fee of the transaction could not be estimated
+	FeeEstimate(subxt::Error),
+	/// The prover does not have the balance to pay the fee of the transaction
+	CannotPayFee { fee: Balance, free: Balance },
+	/// The transaction failed to be submitted
+	Chain(subxt::Error),
+	/// The request could not be claimed
+	Claim(subxt::Error),
+}
+
+impl fmt::Display for SubmitError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			SubmitError::InvalidSigningKey(e) => write!(f, "invalid signing key: {e}"),
+			SubmitError::FeeEstimate(e) => write!(f, "could not estimate the fee: {e}"),
+			SubmitError::CannotPayFee { fee, free } =>
+				write!(f, "the fee of {fee} is more than the free balance {free} of the prover"),
+			SubmitError::Chain(e) => write!(f, "could not submit the proof: {e}"),
+			SubmitError::Claim(e) => write!(f, "could not claim the request: {e}"),
+		}
+	}
+}
+
+impl std::error::Error for SubmitError {}
+
+#[derive(Decode)]
+struct Weight {
+	#[codec(compact)]
+	_ref_time: u64,
+	#[codec(compact)]
+	_proof_size: u64,
+}
+
+// `RuntimeDispatchInfo` as returned by `TransactionPaymentApi_query_info`
+#[derive(Decode)]
+struct DispatchInfo {
+	_weight: Weight,
+	_class: u8,
+	partial_fee: Balance,
+}
+
+/// The signer of transactions for a signing key
+pub fn signer(signing_key: &str) -> Result<PairSigner<ChainConfig, SubxtPair>, SubmitError> {
+	let restored_key = SubxtPair::from_string(signing_key, None)
+		.map_err(|e| SubmitError::InvalidSigningKey(format!("{:?}", e)))?;
+
+	Ok(PairSigner::new(restored_key))
+}
+
+/// Claim the exclusive right to fulfill a request, waiting for the claim to be finalized
+pub async fn claim_request(
+	api: &ApiType,
+	request_id: RequestId,
+	signing_key: &str,
+) -> Result<(), SubmitError> {
+	let signer = signer(signing_key)?;
+
+	api.tx()
+		.sign_and_submit_then_watch_default(&prover_mgmt::claim_request(request_id), &signer)
+		.await
+		.map_err(SubmitError::Claim)?
+		.wait_for_finalized_success()
+		.await
+		.map_err(SubmitError::Claim)?;
+	Ok(())
+}
+
+/// Submit a proof fulfilling a request, returning the progress of its transaction.
+///
+/// The fee of the transaction is estimated first, and the proof is only submitted if the prover
+/// can pay it.
+pub async fn submit_proof(
+	api: &ApiType,
+	image_id: ImageId,
+	request_id: RequestId,
+	session_receipt: &SessionReceipt,
+	signing_key: &str,
+) -> Result<TxProgress<ChainConfig, ApiType>, SubmitError> {
+	let substrate_session_receipt = session_receipt
+		.segments
+		.iter()
+		.map(|SegmentReceipt { seal, index }| (seal.clone(), *index))
+		.collect();
+
+	let signer = signer(signing_key)?;
+
+	let extrinsic = api
+		.tx()
+		.create_signed(
+			// Upload the proof
+			&prover_mgmt::store_and_verify_proof(
+				image_id,
+				Some(request_id),
+				substrate_session_receipt,
+				session_receipt.journal.clone(),
+			),
+			&signer,
+			Default::default(),
+		)
+		.await
+		.map_err(SubmitError::Chain)?;
+
+	let fee = query_fee(api, extrinsic.encoded()).await.map_err(SubmitError::FeeEstimate)?;
+	let free = api
+		.storage()
+		.fetch_or_default(&substrate_node::storage().system().account(signer.account_id()), None)
+		.await
+		.map_err(SubmitError::Chain)?
+		.data
+		.free;
+	if fee > free {
+		return Err(SubmitError::CannotPayFee { fee, free })
+	}
+	println!("Submitting proof for request {request_id}, for a fee of {fee}");
+
+	extrinsic.submit_and_watch().await.map_err(SubmitError::Chain)
+}
+
+/// Fee of a signed extrinsic, as estimated by the runtime
+async fn query_fee(api: &ApiType, extrinsic: &[u8]) -> Result<Balance, subxt::Error> {
+	let mut call_parameters = extrinsic.to_vec();
+	(extrinsic.len() as u32).encode_to(&mut call_parameters);
+
+	let info: Bytes = api
+		.rpc()
+		.request(
+			"state_call",
+			rpc_params!["TransactionPaymentApi_query_info", Bytes(call_parameters)],
+		)
+		.await?;
+	Ok(DispatchInfo::decode(&mut &info[..])?.partial_fee)
+}
@@ -0,0 +1,409 @@
+//! Long-running prover which fulfills requests as they are made.
+//!
+//! Finalized blocks are scanned for `ProofRequested` events, and the requests they announce are
+//! queued and proven one at a time. Requests and the last scanned block are tracked in the job
+//! database, so that when the connection to the node drops or the prover restarts, it catches up
+//! with the blocks finalized in the meantime and resumes its unfinished jobs. Requests which were
+//! already open when the prover started are queued from the `prover_openRequests` RPC.
+//!
+//! Requests are claimed before being proven, so that no other prover fulfills them in the
+//! meantime. Requests claimed by other provers, or still being auctioned, are deferred until a
+//! `ClaimLapsed` or `AuctionClosed` event reopens them.
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use serde_json::Value;
+use subxt::events::Events;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::{
+	chain::{
+		claim_request, get_manifest, get_program, get_proof_request, get_prover, is_auctioned,
+		open_requests, request_args, signer, submit_proof, ApiType, ChainConfig, RequestId,
+		SubmitError,
+	},
+	jobs::{Error, JobDb, JobState},
+	policy::{Decision, Pricing},
+	prover_mgmt::{AuctionClosed, ClaimLapsed, ProofRequested},
+	proving::{execute_program, execution_stats, prove_session, verify_receipt, ExecutionError},
+	receipt::ReceiptFile,
+};
+
+/// How long to wait before reconnecting to the node
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Number of open requests fetched at once on startup
+const OPEN_REQUESTS_PAGE: u32 = 100;
+
+pub async fn run(
+	url: String,
+	db: JobDb,
+	pricing: Pricing,
+	signing_key: String,
+) -> Result<(), Error> {
+	// Only registered provers may claim requests and be rewarded for them
+	let api = ApiType::from_url(&url).await?;
+	let account = signer(&signing_key)?.account_id().clone();
+	match get_prover(&api, &account).await? {
+		Some(prover) if prover.active =>
+			println!("Proving as {account}, registered to prove up to {} cycles", prover.max_cycles),
+		Some(_) =>
+			return Err(format!(
+				"Prover {account} is unbonding, or its stake was slashed below the minimum"
+			)
+			.into()),
+		None => return Err(format!("{account} is not registered as a prover").into()),
+	}
+
+	let (queue, jobs) = mpsc::unbounded_channel();
+
+	// Resume the jobs which were interrupted by the last shutdown
+	for job in db.pending()? {
+		println!("Resuming request {} ({})", job.request_id, job.state);
+		let _ = queue.send(job.request_id);
+	}
+	// Requests made before the first block the prover scans would be missed otherwise
+	queue_open_requests(&api, &db, &queue).await?;
+	tokio::spawn(work(url.clone(), db.clone(), pricing, signing_key, jobs));
+
+	loop {
+		match listen(&url, &db, &queue).await {
+			Ok(()) => println!("Finalized block subscription ended, reconnecting"),
+			Err(e) => println!("Lost connection to node: {e}, reconnecting"),
+		}
+		tokio::time::sleep(RECONNECT_DELAY).await;
+	}
+}
+
+async fn listen(url: &str, db: &JobDb, queue: &UnboundedSender<RequestId>) -> Result<(), Error> {
+	let api = ApiType::from_url(url).await?;
+	let mut blocks = api.blocks().subscribe_finalized().await?;
+	println!("Listening for proof requests at {url}");
+
+	while let Some(block) = blocks.next().await {
+		let block = block?;
+		let number = block.header().number;
+
+		// Catch up with the blocks finalized while disconnected
+		for missed in db.last_block()?.map_or(number, |last| last + 1)..number {
+			let hash = api
+				.rpc()
+				.block_hash(Some(missed.into()))
+				.await?
+				.ok_or_else(|| format!("Block {missed} not found"))?;
+			queue_requests(&api.events().at(Some(hash)).await?, db, queue)?;
+			db.set_last_block(missed)?;
+		}
+
+		queue_requests(&block.events().await?, db, queue)?;
+		db.set_last_block(number)?;
+	}
+	Ok(())
+}
+
+/// Queue the requests open at the best block which were not seen yet
+async fn queue_open_requests(
+	api: &ApiType,
+	db: &JobDb,
+	queue: &UnboundedSender<RequestId>,
+) -> Result<(), Error> {
+	let mut from = 0;
+	loop {
+		let page = open_requests(api, from, OPEN_REQUESTS_PAGE).await?;
+		for &request_id in &page {
+			queue_request(request_id, db, queue)?;
+		}
+		match page.last() {
+			Some(last) if page.len() as u32 == OPEN_REQUESTS_PAGE => from = last + 1,
+			_ => return Ok(()),
+		}
+	}
+}
+
+fn queue_requests(
+	events: &Events<ChainConfig>,
+	db: &JobDb,
+	queue: &UnboundedSender<RequestId>,
+) -> Result<(), Error> {
+	for event in events.find::<ProofRequested>() {
+		queue_request(event?.request_id, db, queue)?;
+	}
+
+	// Deferred requests may be claimed again once their claim lapses or their auction closes
+	for event in events.find::<ClaimLapsed>() {
+		requeue_request(event?.request, db, queue)?;
+	}
+	for event in events.find::<AuctionClosed>() {
+		requeue_request(event?.request, db, queue)?;
+	}
+	Ok(())
+}
+
+fn queue_request(
+	request_id: RequestId,
+	db: &JobDb,
+	queue: &UnboundedSender<RequestId>,
+) -> Result<(), Error> {
+	// Requests already seen are either done or resumed on startup
+	if db.queue(request_id)? {
+		println!("Queueing request {request_id}");
+		// The worker only stops along with the process
+		let _ = queue.send(request_id);
+	}
+	Ok(())
+}
+
+fn requeue_request(
+	request_id: RequestId,
+	db: &JobDb,
+	queue: &UnboundedSender<RequestId>,
+) -> Result<(), Error> {
+	if db.requeue(request_id)? {
+		println!("Queueing deferred request {request_id} again");
+		let _ = queue.send(request_id);
+	}
+	Ok(())
+}
+
+async fn work(
+	url: String,
+	db: JobDb,
+	pricing: Pricing,
+	signing_key: String,
+	mut jobs: UnboundedReceiver<RequestId>,
+) {
+	while let Some(request_id) = jobs.recv().await {
+		// Jobs pick up from where they were left, so they are retried until the node is back
+		while let Err(e) = fulfill(&url, &db, &pricing, request_id, &signing_key).await {
+			if is_connection_error(&*e) {
+				println!("Lost connection to node while fulfilling request {request_id}: {e}");
+				tokio::time::sleep(RECONNECT_DELAY).await;
+				continue
+			}
+
+			println!("Failed to fulfill request {request_id}: {e}");
+			if let Err(e) = db.set_state(request_id, JobState::Failed(e.to_string())) {
+				println!("Failed to record failure of request {request_id}: {e}");
+			}
+			break
+		}
+	}
+}
+
+/// Whether an error is a failure to reach the node, rather than a failure of the job itself
+fn is_connection_error(e: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+	let subxt_error = match e.downcast_ref::<SubmitError>() {
+		Some(SubmitError::FeeEstimate(e) | SubmitError::Chain(e) | SubmitError::Claim(e)) =>
+			Some(e),
+		Some(_) => None,
+		None => e.downcast_ref::<subxt::Error>(),
+	};
+	matches!(subxt_error, Some(subxt::Error::Io(_) | subxt::Error::Rpc(_)))
+}
+
+/// Prove a request and submit its proof, picking up from wherever its job was left
+pub async fn fulfill(
+	url: &str,
+	db: &JobDb,
+	pricing: &Pricing,
+	request_id: RequestId,
+	signing_key: &str,
+) -> Result<(), Error> {
+	match prove(url, db, pricing, request_id, Some(signing_key)).await? {
+		Some(receipt_file) => submit(url, db, &receipt_file, signing_key).await,
+		None => Ok(()),
+	}
+}
+
+/// Prove a request, unless it is not worth proving or no longer open. The request is claimed with
+/// `signing_key` before being proven; without one, only unclaimed requests are proven, and they are
+/// left unclaimed.
+pub async fn prove(
+	url: &str,
+	db: &JobDb,
+	pricing: &Pricing,
+	request_id: RequestId,
+	signing_key: Option<&str>,
+) -> Result<Option<ReceiptFile>, Error> {
+	db.queue(request_id)?;
+	let job = db.job(request_id)?.ok_or("Job was just queued")?;
+
+	let api = ApiType::from_url(url).await?;
+	let proof_request = match get_proof_request(&api, request_id).await? {
+		Some(proof_request) => proof_request,
+		// A proof submitted before a restart may have been finalized in the meantime
+		None if job.state == JobState::Submitted => {
+			println!("Request {request_id} was fulfilled");
+			db.set_state(request_id, JobState::Finalized)?;
+			return Ok(None)
+		},
+		// Jobs which are done, or left for the operator, keep their state
+		None => {
+			println!("Request {request_id} is no longer open, skipping it");
+			if matches!(job.state, JobState::Queued | JobState::Executing | JobState::Proving) {
+				db.set_state(request_id, JobState::Failed("Request is no longer open".into()))?;
+			}
+			return Ok(None)
+		},
+	};
+
+	let image_id = proof_request.image_id;
+	db.set_image_id(request_id, image_id)?;
+
+	if let Some(receipt_file) = db.load_receipt(request_id)? {
+		println!("Reusing the receipt already proven for request {request_id}");
+		return Ok(Some(receipt_file))
+	}
+
+	// Claims lapse and auctions close, after which the request may be claimed again
+	let account = signing_key.map(signer).transpose()?.map(|signer| signer.account_id().clone());
+	let claimed = match &proof_request.claim {
+		Some(claim) if Some(&claim.prover) != account.as_ref() => {
+			let reason = format!("claimed by {} until block {}", claim.prover, claim.expires_at);
+			record_decision(db, request_id, Decision::Defer(reason))?;
+			return Ok(None)
+		},
+		claim => claim.is_some(),
+	};
+	if is_auctioned(&api, request_id).await? {
+		record_decision(db, request_id, Decision::Defer("the request is being auctioned".into()))?;
+		return Ok(None)
+	}
+
+	// Requests whose reward is too low are not worth executing either
+	let reward = proof_request.reward;
+	if !record_decision(db, request_id, pricing.decide_reward(reward))? {
+		return Ok(None)
+	}
+
+	let program = get_program(&api, image_id).await?.ok_or("Onchain program should exist")?;
+	let program_args = request_args(proof_request);
+	println!("Executing request {request_id} with args {:?}", program_args);
+
+	// Executing and proving take a while, and must not block the runtime
+	db.set_state(request_id, JobState::Executing)?;
+	let max_cycles = pricing.max_cycles;
+	let session = match tokio::task::spawn_blocking(move || {
+		execute_program(program, program_args, max_cycles)
+	})
+	.await?
+	{
+		Ok(session) => session,
+		Err(e @ ExecutionError::CycleLimit(_)) => {
+			record_decision(db, request_id, Decision::Skip(e.to_string()))?;
+			return Ok(None)
+		},
+		Err(e) => return Err(e.into()),
+	};
+
+	// Only prove requests whose reward is worth the cycles of their execution
+	let stats = execution_stats(&session);
+	db.set_cycles(request_id, stats.padded_cycles)?;
+	let decision = pricing.decide(reward, &stats);
+	if decision == Decision::Prove {
+		println!(
+			"Proving request {request_id}: {} padded cycles in {} segments, for a reward of \
+			 {reward} at a cost of {}",
+			stats.padded_cycles,
+			stats.segments,
+			pricing.cost(stats.padded_cycles)
+		);
+	}
+	if !record_decision(db, request_id, decision)? {
+		return Ok(None)
+	}
+
+	// Keep other provers from fulfilling the request while this one proves it
+	if let (false, Some(signing_key)) = (claimed, signing_key) {
+		match claim_request(&api, request_id, signing_key).await {
+			Ok(()) => println!("Claimed request {request_id}"),
+			Err(e) if is_connection_error(&e) => return Err(e.into()),
+			// Another prover may have claimed the request first
+			Err(e) => {
+				record_decision(db, request_id, Decision::Defer(e.to_string()))?;
+				return Ok(None)
+			},
+		}
+	}
+
+	db.set_state(request_id, JobState::Proving)?;
+	let db = db.clone();
+	let receipt_file = tokio::task::spawn_blocking(move || -> Result<_, Error> {
+		let receipt_file = ReceiptFile::new(image_id, request_id, &prove_session(session)?);
+		db.save_receipt(&receipt_file)?;
+		Ok(receipt_file)
+	})
+	.await??;
+	Ok(Some(receipt_file))
+}
+
+/// Record a decision not to prove a request in its job. Returns whether to go on proving it.
+fn record_decision(db: &JobDb, request_id: RequestId, decision: Decision) -> Result<bool, Error> {
+	match decision {
+		Decision::Prove => return Ok(true),
+		Decision::Skip(reason) => {
+			println!("Skipping request {request_id}: {reason}");
+			db.set_state(request_id, JobState::Skipped(reason))?;
+		},
+		Decision::Defer(reason) => {
+			println!("Deferring request {request_id}: {reason}");
+			db.set_state(request_id, JobState::Deferred(reason))?;
+		},
+	}
+	Ok(false)
+}
+
+/// Submit a proven receipt, once it is saved in the job database
+pub async fn submit(
+	url: &str,
+	db: &JobDb,
+	receipt_file: &ReceiptFile,
+	signing_key: &str,
+) -> Result<(), Error> {
+	let request_id = receipt_file.request_id;
+	let image_id = receipt_file.image_id;
+	let session_receipt = receipt_file.session_receipt();
+
+	// Failures past this point leave the receipt on disk, for the operator to inspect. Failures to
+	// reach the node are passed on as is, for the submission to be retried.
+	let receipt_path = db
+		.job(request_id)?
+		.and_then(|job| job.receipt)
+		.ok_or("Proven receipt should be saved")?;
+	let kept = |context: &str, e: Error| -> Error {
+		if is_connection_error(&*e) {
+			return e
+		}
+		format!("{context}{e} (receipt kept at {})", receipt_path.display()).into()
+	};
+
+	// Never pay fees for a proof the chain would reject
+	verify_receipt(&session_receipt, image_id)
+		.map_err(|e| kept("receipt failed local verification: ", e.into()))?;
+
+	let api = ApiType::from_url(url).await?;
+	let progress = submit_proof(&api, image_id, request_id, &session_receipt, signing_key)
+		.await
+		.map_err(|e| kept("", e.into()))?;
+	db.add_tx_hash(request_id, format!("{:?}", progress.extrinsic_hash()))?;
+	progress
+		.wait_for_finalized_success()
+		.await
+		.map_err(|e| kept("proof transaction failed: ", e.into()))?;
+	db.set_state(request_id, JobState::Finalized)?;
+	println!("Proof for request {request_id} finalized");
+
+	// Programs uploaded with a manifest tell what their journal holds
+	match get_manifest(&api, image_id).await {
+		Ok(Some(manifest)) if !manifest.outputs.is_empty() => match manifest
+			.decode_journal(&receipt_file.journal)
+		{
+			Ok(outputs) => println!("Outputs of request {request_id}: {}", Value::Object(outputs)),
+			Err(e) => println!("Journal of request {request_id} does not match its manifest: {e}"),
+		},
+		Ok(_) => {},
+		Err(e) => println!("Failed to fetch the manifest of request {request_id}: {e}"),
+	}
+	Ok(())
+}
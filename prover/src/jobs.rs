@@ -0,0 +1,237 @@
+//! Local database of the requests seen by the prover.
+//!
+//! Every request the prover picks up is tracked as a job, from being queued to its proof being
+//! finalized onchain. Receipts are written next to the database as soon as they are proven, so a
+//! prover which restarts resumes its jobs instead of proving them all over again.
+
+use std::{
+	fmt, fs,
+	path::{Path, PathBuf},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	chain::{ImageId, RequestId},
+	receipt::ReceiptFile,
+};
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Key under which the last finalized block scanned for requests is stored
+const LAST_BLOCK_KEY: &[u8] = b"last_block";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobState {
+	/// Seen onchain, waiting for its turn
+	Queued,
+	/// Running the program to record its execution
+	Executing,
+	/// Not worth proving at the prices the prover was run with, or claimed or auctioned by others.
+	/// Reconsidered when a claim lapses or an auction closes, and on restart.
+	Deferred(String),
+	/// Proving the recorded execution
+	Proving,
+	/// Receipt exported to a file, to be submitted from another host
+	Exported(PathBuf),
+	/// Proof submitted in a transaction which is not finalized yet
+	Submitted,
+	/// Proof finalized onchain
+	Finalized,
+	/// Never to be proven by this prover
+	Skipped(String),
+	/// Given up on the request
+	Failed(String),
+}
+
+impl JobState {
+	/// Whether the job still has work left to do
+	pub fn is_pending(&self) -> bool {
+		!matches!(
+			self,
+			JobState::Exported(_) |
+				JobState::Finalized |
+				JobState::Skipped(_) |
+				JobState::Failed(_)
+		)
+	}
+}
+
+impl fmt::Display for JobState {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			JobState::Queued => write!(f, "queued"),
+			JobState::Executing => write!(f, "executing"),
+			JobState::Deferred(reason) => write!(f, "deferred: {reason}"),
+			JobState::Proving => write!(f, "proving"),
+			JobState::Exported(path) => write!(f, "exported to {}", path.display()),
+			JobState::Submitted => write!(f, "submitted"),
+			JobState::Finalized => write!(f, "finalized"),
+			JobState::Skipped(reason) => write!(f, "skipped: {reason}"),
+			JobState::Failed(reason) => write!(f, "failed: {reason}"),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Job {
+	pub request_id: RequestId,
+	// Only known once the request was fetched from chain
+	pub image_id: Option<ImageId>,
+	pub state: JobState,
+	// Padded cycles counted by executing the program, before deciding whether to prove it
+	#[serde(default)]
+	pub cycles: Option<u64>,
+	// Where the receipt was written, once proven
+	pub receipt: Option<PathBuf>,
+	// Hex encoded hashes of every transaction the proof was submitted in
+	pub tx_hashes: Vec<String>,
+	// Seconds since the unix epoch
+	pub created_at: u64,
+	pub updated_at: u64,
+}
+
+#[derive(Clone)]
+pub struct JobDb {
+	db: sled::Db,
+	jobs: sled::Tree,
+	receipts: PathBuf,
+}
+
+impl JobDb {
+	/// Open the database in `dir`, creating it if needed
+	pub fn open(dir: impl AsRef<Path>) -> Result<Self, Error> {
+		let dir = dir.as_ref();
+		let receipts = dir.join("receipts");
+		fs::create_dir_all(&receipts)?;
+
+		let db = sled::open(dir.join("jobs"))?;
+		let jobs = db.open_tree("jobs")?;
+		Ok(JobDb { db, jobs, receipts })
+	}
+
+	pub fn job(&self, request_id: RequestId) -> Result<Option<Job>, Error> {
+		match self.jobs.get(request_id.to_be_bytes())? {
+			Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+			None => Ok(None),
+		}
+	}
+
+	/// All jobs, ordered by request id
+	pub fn jobs(&self) -> Result<Vec<Job>, Error> {
+		self.jobs
+			.iter()
+			.values()
+			.map(|bytes| Ok(serde_json::from_slice(&bytes?)?))
+			.collect()
+	}
+
+	/// Jobs which were interrupted before finishing
+	pub fn pending(&self) -> Result<Vec<Job>, Error> {
+		Ok(self.jobs()?.into_iter().filter(|job| job.state.is_pending()).collect())
+	}
+
+	/// Start tracking a request. Returns false if it was already seen.
+	pub fn queue(&self, request_id: RequestId) -> Result<bool, Error> {
+		let now = now();
+		let job = Job {
+			request_id,
+			image_id: None,
+			state: JobState::Queued,
+			cycles: None,
+			receipt: None,
+			tx_hashes: Vec::new(),
+			created_at: now,
+			updated_at: now,
+		};
+		let inserted = self
+			.jobs
+			.compare_and_swap(
+				request_id.to_be_bytes(),
+				None as Option<&[u8]>,
+				Some(serde_json::to_vec(&job)?),
+			)?
+			.is_ok();
+		self.jobs.flush()?;
+		Ok(inserted)
+	}
+
+	/// Queue a deferred job again. Returns false if the job is not deferred.
+	pub fn requeue(&self, request_id: RequestId) -> Result<bool, Error> {
+		match self.job(request_id)? {
+			Some(Job { state: JobState::Deferred(_), .. }) => {
+				self.set_state(request_id, JobState::Queued)?;
+				Ok(true)
+			},
+			_ => Ok(false),
+		}
+	}
+
+	pub fn set_image_id(&self, request_id: RequestId, image_id: ImageId) -> Result<(), Error> {
+		self.update(request_id, |job| job.image_id = Some(image_id))
+	}
+
+	pub fn set_cycles(&self, request_id: RequestId, cycles: u64) -> Result<(), Error> {
+		self.update(request_id, |job| job.cycles = Some(cycles))
+	}
+
+	pub fn set_state(&self, request_id: RequestId, state: JobState) -> Result<(), Error> {
+		self.update(request_id, |job| job.state = state)
+	}
+
+	pub fn add_tx_hash(&self, request_id: RequestId, tx_hash: String) -> Result<(), Error> {
+		self.update(request_id, |job| {
+			job.tx_hashes.push(tx_hash);
+			job.state = JobState::Submitted;
+		})
+	}
+
+	/// Write the receipt of a job to disk and record where it is
+	pub fn save_receipt(&self, receipt_file: &ReceiptFile) -> Result<PathBuf, Error> {
+		let request_id = receipt_file.request_id;
+		let path = self.receipts.join(format!("{request_id}.bin"));
+		receipt_file.write(&path)?;
+
+		let stored = path.clone();
+		self.update(request_id, |job| job.receipt = Some(stored))?;
+		Ok(path)
+	}
+
+	/// Read back the receipt of a job, if it was proven
+	pub fn load_receipt(&self, request_id: RequestId) -> Result<Option<ReceiptFile>, Error> {
+		match self.job(request_id)?.and_then(|job| job.receipt) {
+			Some(path) => Ok(Some(ReceiptFile::read(path)?)),
+			None => Ok(None),
+		}
+	}
+
+	/// Last finalized block whose requests were queued
+	pub fn last_block(&self) -> Result<Option<u32>, Error> {
+		match self.db.get(LAST_BLOCK_KEY)? {
+			Some(bytes) => Ok(Some(u32::from_be_bytes(bytes.as_ref().try_into()?))),
+			None => Ok(None),
+		}
+	}
+
+	pub fn set_last_block(&self, number: u32) -> Result<(), Error> {
+		self.db.insert(LAST_BLOCK_KEY, &number.to_be_bytes())?;
+		self.db.flush()?;
+		Ok(())
+	}
+
+	fn update(&self, request_id: RequestId, f: impl FnOnce(&mut Job)) -> Result<(), Error> {
+		let mut job = self.job(request_id)?.ok_or("Job should be queued before being updated")?;
+		f(&mut job);
+		job.updated_at = now();
+		self.jobs.insert(request_id.to_be_bytes(), serde_json::to_vec(&job)?)?;
+		self.jobs.flush()?;
+		Ok(())
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -0,0 +1,55 @@
+//! Proving of local programs, with the same pipeline as onchain requests but without a chain.
+
+use std::{fs, path::Path, time::Instant};
+
+use program_args::Manifest;
+
+use crate::{
+	jobs::Error,
+	proving::{compute_image_id, execute_program, execution_stats, prove_session, verify_receipt},
+};
+
+/// Execute, prove and verify the ELF at `elf` with the args of `args`. Without a schema, `args` is
+/// a JSON array of arrays of words like the args of a request, and with one it holds the values of
+/// the schema's fields. The schema may be a whole manifest, in which case the outputs of the
+/// journal are decoded too.
+pub fn run(elf: &Path, args: Option<&Path>, schema: Option<&Path>) -> Result<(), Error> {
+	// Programs are stored onchain as a bincode-serialized ELF
+	let program = bincode::serialize(&fs::read(elf)?)?;
+	let image_id = compute_image_id(&program).ok_or("Not a valid RISC Zero ELF")?;
+	let manifest = schema.map(Manifest::from_file).transpose()?;
+	let program_args: Vec<Vec<u32>> = match (args, &manifest) {
+		(Some(args), Some(manifest)) => manifest.schema().encode_file(args)?,
+		(Some(args), None) => serde_json::from_slice(&fs::read(args)?)?,
+		(None, _) => Vec::new(),
+	};
+	println!("Image id: {}", hex::encode(bincode::serialize(&image_id)?));
+	println!("Passing args to program: {:?}", program_args);
+
+	let start = Instant::now();
+	let session = execute_program(program, program_args, None)?;
+	let execution_time = start.elapsed();
+
+	let stats = execution_stats(&session);
+	println!("Cycles: {}", stats.cycles);
+	println!("Padded cycles: {}", stats.padded_cycles);
+	println!("Segments: {}", stats.segments);
+
+	let start = Instant::now();
+	let session_receipt = prove_session(session)?;
+	let proving_time = start.elapsed();
+
+	let start = Instant::now();
+	verify_receipt(&session_receipt, image_id)?;
+	let verification_time = start.elapsed();
+
+	println!("Journal: {}", hex::encode(&session_receipt.journal));
+	if let Some(manifest) = manifest.filter(|manifest| !manifest.outputs.is_empty()) {
+		let outputs = manifest.decode_journal(&session_receipt.journal)?;
+		println!("Outputs: {}", serde_json::Value::Object(outputs));
+	}
+	println!("Execution took {:?}", execution_time);
+	println!("Proving took {:?}", proving_time);
+	println!("Verification took {:?}", verification_time);
+	Ok(())
+}
@@ -1,132 +1,217 @@
-use clap::Parser;
-use risc0_zkvm::{Executor, ExecutorEnv, SegmentReceipt, SessionReceipt};
-use subxt::{
-	config::WithExtrinsicParams,
-	ext::sp_core::{sr25519::Pair as SubxtPair, Pair as SubxtPairT},
-	tx::{BaseExtrinsicParams, PairSigner, PlainTip},
-	OnlineClient, PolkadotConfig, SubstrateConfig,
-};
-
-use crate::substrate_node::runtime_types::pallet_prover_mgmt::pallet::ProofRequest;
-
-// // Runtime types, etc
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use program_args::Manifest;
+
+mod chain;
+mod daemon;
+mod jobs;
+mod local;
+mod policy;
+mod prover_mgmt;
+mod proving;
+mod receipt;
+
+use chain::{get_manifest, parse_image_id, ApiType, ImageId, RequestId};
+use jobs::{Error, JobDb, JobState};
+use policy::Pricing;
+use receipt::ReceiptFile;
+
+// Runtime types, etc. The `ProverMgmt` pallet is reached through `prover_mgmt` instead.
 #[subxt::subxt(runtime_metadata_path = "./metadata.scale")]
 pub mod substrate_node {}
 
-type ApiType = OnlineClient<
-	WithExtrinsicParams<SubstrateConfig, BaseExtrinsicParams<SubstrateConfig, PlainTip>>,
->;
-
-type ImageId = [u32; 8];
-
-async fn get_program(api: &ApiType, image_id: ImageId) -> Result<Option<Vec<u8>>, subxt::Error> {
-	let query = substrate_node::storage().prover_mgmt().programs(image_id);
-
-	api.storage().fetch(&query, None).await
-}
-
-async fn get_proof_request(
-	api: &ApiType,
-	image_id: ImageId,
-) -> Result<Option<ProofRequest>, subxt::Error> {
-	let query = substrate_node::storage().prover_mgmt().proof_requests(image_id);
-
-	api.storage().fetch(&query, None).await
-}
-
-// Prove the program which was given as serialized bytes
-fn prove_program_execution(onchain_program: Vec<u8>, args: Vec<Vec<u32>>) -> SessionReceipt {
-	let mut envbuilder = ExecutorEnv::builder();
-	args.iter().for_each(|a| {
-		envbuilder.add_input(a);
-	});
-
-	let env = envbuilder.build();
-
-	let mut executor =
-		Executor::from_elf(env.clone(), bincode::deserialize(&onchain_program).unwrap()).unwrap();
-
-	println!("Starting session");
-	let session = executor.run().unwrap();
-	println!("Now proving execution");
-	let receipt = session.prove().unwrap();
-	println!("Done proving");
-	receipt
-}
-
-async fn upload_proof(
-	api: ApiType,
-	image_id: ImageId,
-	session_receipt: SessionReceipt,
-	signing_key: String,
-) {
-	let substrate_session_receipt = session_receipt
-		.segments
-		.into_iter()
-		.map(|SegmentReceipt { seal, index }| (seal, index))
-		.collect();
-
-	let restored_key = SubxtPair::from_string(&signing_key, None).unwrap();
-
-	let signer = PairSigner::new(restored_key);
-
-	api.tx()
-		.sign_and_submit_then_watch_default(
-			&substrate_node::tx()
-				.prover_mgmt()
-				// Upload the proof
-				.store_and_verify_proof(
-					image_id,
-					substrate_session_receipt,
-					session_receipt.journal,
-				),
-			&signer,
-		)
-		.await
-		.unwrap()
-		.wait_for_finalized()
-		.await
-		.unwrap();
-	println!("Proof uploaded");
-}
-
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-	/// The hex-encoded, bincode-serialized image id of the onchain program to prove
-	#[arg(short, long)]
-	image_id: String,
-	/// The Secret key of prover to sign and submit proof to chain.
-	#[arg(env)]
-	signing_key: String,
+	/// Websocket url of the node
+	#[arg(long, default_value = "ws://127.0.0.1:9944")]
+	url: String,
+	/// Directory of the job database and of the proven receipts
+	#[arg(long, default_value = "prover-db")]
+	db: PathBuf,
+	#[command(flatten)]
+	pricing: Pricing,
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+	/// Prove a single onchain request and submit its proof
+	Prove {
+		/// The id of the onchain proof request to fulfill
+		#[arg(short, long)]
+		request_id: RequestId,
+		/// Write the receipt to this file instead of submitting it
+		#[arg(long)]
+		out: Option<PathBuf>,
+		/// The Secret key of prover to sign and submit proof to chain.
+		#[arg(env, required_unless_present = "out")]
+		signing_key: Option<String>,
+	},
+	/// Submit the proof of a receipt file written by `prove --out`
+	Submit {
+		/// The receipt file to submit
+		#[arg(long)]
+		receipt: PathBuf,
+		/// The Secret key of prover to sign and submit proof to chain.
+		#[arg(env)]
+		signing_key: String,
+	},
+	/// Keep proving requests as they are made onchain
+	Daemon {
+		/// The Secret key of prover to sign and submit proofs to chain.
+		#[arg(env)]
+		signing_key: String,
+	},
+	/// List the requests seen by the prover and the state of their jobs
+	Jobs,
+	/// Prove a local program, without touching the chain
+	Local {
+		/// The RISC Zero ELF of the program
+		#[arg(long)]
+		elf: PathBuf,
+		/// JSON file with the args to pass to the program, as an array of arrays of words, or JSON
+		/// or TOML file with the values of the fields of `--schema`
+		#[arg(long)]
+		args: Option<PathBuf>,
+		/// JSON or TOML file with the schema of the program's inputs, or its whole manifest
+		#[arg(long, requires = "args")]
+		schema: Option<PathBuf>,
+	},
+	/// Check values against the schema of a program's inputs, and print them as the args of a
+	/// request
+	EncodeArgs {
+		/// JSON or TOML file with the schema of the program's inputs
+		#[arg(long, required_unless_present = "image_id")]
+		schema: Option<PathBuf>,
+		/// Read the schema from the manifest of this onchain program instead, given as hex
+		#[arg(long, conflicts_with = "schema", value_parser = parse_image_id)]
+		image_id: Option<ImageId>,
+		/// JSON or TOML file with the value of every field of the schema
+		#[arg(long)]
+		values: PathBuf,
+	},
 }
 
 #[tokio::main]
 async fn main() {
 	let cli_args = Args::parse();
+	let open_db = || JobDb::open(&cli_args.db).expect("Job database should open");
+
+	match cli_args.command {
+		Command::Prove { request_id, out, signing_key } => {
+			let db = open_db();
+			let result = match (out, signing_key) {
+				(Some(out), _) =>
+					export_receipt(&cli_args.url, &db, &cli_args.pricing, request_id, out).await,
+				(None, Some(signing_key)) =>
+					daemon::fulfill(&cli_args.url, &db, &cli_args.pricing, request_id, &signing_key)
+						.await,
+				(None, None) => unreachable!("Signing key is required without --out"),
+			};
+			if let Err(e) = result {
+				fail(&db, request_id, e);
+			}
+		},
+		Command::Submit { receipt, signing_key } => {
+			let receipt_file = ReceiptFile::read(&receipt).unwrap_or_else(|e| {
+				eprintln!("Failed to read receipt {}: {e}", receipt.display());
+				std::process::exit(1);
+			});
+			let request_id = receipt_file.request_id;
+			let db = open_db();
+			if let Err(e) = submit_receipt(&cli_args.url, &db, receipt_file, &signing_key).await {
+				fail(&db, request_id, e);
+			}
+		},
+		Command::Daemon { signing_key } =>
+			daemon::run(cli_args.url, open_db(), cli_args.pricing, signing_key)
+				.await
+				.unwrap(),
+		Command::Jobs =>
+			for job in open_db().jobs().unwrap() {
+				println!(
+					"request {}: {} (image id: {}, padded cycles: {}, receipt: {}, transactions: [{}])",
+					job.request_id,
+					job.state,
+					job.image_id.map_or("unknown".into(), |image_id| format!("{:?}", image_id)),
+					job.cycles.map_or("unknown".into(), |cycles| cycles.to_string()),
+					job.receipt.map_or("none".into(), |path| path.display().to_string()),
+					job.tx_hashes.join(", "),
+				);
+			},
+		Command::Local { elf, args, schema } =>
+			if let Err(e) = local::run(&elf, args.as_deref(), schema.as_deref()) {
+				eprintln!("Failed to prove {}: {e}", elf.display());
+				std::process::exit(1);
+			},
+		Command::EncodeArgs { schema, image_id, values } =>
+			match encode_args(&cli_args.url, schema, image_id, &values).await {
+				Ok(program_args) => println!("{}", serde_json::to_string(&program_args).unwrap()),
+				Err(e) => {
+					eprintln!("Invalid args {}: {e}", values.display());
+					std::process::exit(1);
+				},
+			},
+	}
+}
 
-	let hex_decoded = hex::decode(&cli_args.image_id).unwrap();
-	let image_id = bincode::deserialize(&hex_decoded).unwrap();
-
-	let api = OnlineClient::<PolkadotConfig>::new().await.unwrap();
-
-	// listen_for_event_then_prove().await;
-	let program = get_program(&api, image_id).await;
-	let proof_request = get_proof_request(&api, image_id).await;
-
-	println!("Proof request for given image id: {:?}", &proof_request);
-
-	let program_args = proof_request
-		.unwrap()
-		.expect("Args were not provided, or request was not made for program proof")
-		.args;
+/// Prove a request and write its receipt to `out`, to be submitted from another host
+async fn export_receipt(
+	url: &str,
+	db: &JobDb,
+	pricing: &Pricing,
+	request_id: RequestId,
+	out: PathBuf,
+) -> Result<(), Error> {
+	if let Some(receipt_file) = daemon::prove(url, db, pricing, request_id, None).await? {
+		receipt_file.write(&out)?;
+		println!("Receipt for request {request_id} written to {}", out.display());
+		db.set_state(request_id, JobState::Exported(out))?;
+	}
+	Ok(())
+}
 
-	println!("Passing args to program :{:?}", program_args);
+/// Track a receipt proven on another host as a job, and submit it
+async fn submit_receipt(
+	url: &str,
+	db: &JobDb,
+	receipt_file: ReceiptFile,
+	signing_key: &str,
+) -> Result<(), Error> {
+	let request_id = receipt_file.request_id;
+	db.queue(request_id)?;
+	db.set_image_id(request_id, receipt_file.image_id)?;
+	db.save_receipt(&receipt_file)?;
+	daemon::submit(url, db, &receipt_file, signing_key).await
+}
 
-	let session_receipt = prove_program_execution(
-		program.unwrap().expect("Onchain program should exist"),
-		program_args,
-	);
+/// Check values against a schema file, or the manifest of an onchain program, and encode them
+async fn encode_args(
+	url: &str,
+	schema: Option<PathBuf>,
+	image_id: Option<ImageId>,
+	values: &Path,
+) -> Result<Vec<Vec<u32>>, Error> {
+	let manifest = match (schema, image_id) {
+		(Some(schema), _) => Manifest::from_file(schema)?,
+		(None, Some(image_id)) => {
+			let api = ApiType::from_url(url).await?;
+			get_manifest(&api, image_id)
+				.await?
+				.ok_or("Program was not uploaded with a manifest")?
+		},
+		(None, None) => unreachable!("Schema is required without --image-id"),
+	};
+	Ok(manifest.schema().encode_file(values)?)
+}
 
-	upload_proof(api, image_id, session_receipt, cli_args.signing_key).await;
+fn fail(db: &JobDb, request_id: RequestId, e: Error) -> ! {
+	if let Err(e) = db.set_state(request_id, JobState::Failed(e.to_string())) {
+		eprintln!("Failed to record failure of request {request_id}: {e}");
+	}
+	eprintln!("Failed to fulfill request {request_id}: {e}");
+	std::process::exit(1);
 }
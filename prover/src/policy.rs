@@ -0,0 +1,73 @@
+//! Pricing of proofs, and the policy deciding which requests are worth proving.
+
+use crate::proving::ExecutionStats;
+
+pub type Balance = u128;
+
+#[derive(clap::Args, Clone, Debug)]
+pub struct Pricing {
+	/// Price of proving a million cycles, in the smallest unit of the chain's currency
+	#[arg(long, default_value_t = 0)]
+	pub price_per_mcycle: Balance,
+	/// Smallest reward worth proving a request for. Requests with a lower reward are not even
+	/// executed.
+	#[arg(long, default_value_t = 0)]
+	pub min_reward: Balance,
+	/// Largest number of cycles the prover is willing to prove. Executions are stopped once they
+	/// take more.
+	#[arg(long)]
+	pub max_cycles: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Decision {
+	/// The reward covers the cost of proving
+	Prove,
+	/// The request can never be proven by this prover
+	Skip(String),
+	/// The request is not worth proving at the current prices
+	Defer(String),
+}
+
+impl Pricing {
+	/// Cost of proving the given number of cycles, rounded up
+	pub fn cost(&self, cycles: u64) -> Balance {
+		(cycles as Balance)
+			.saturating_mul(self.price_per_mcycle)
+			.saturating_add(999_999) /
+			1_000_000
+	}
+
+	/// Decide from its reward alone whether a request is worth executing
+	pub fn decide_reward(&self, reward: Balance) -> Decision {
+		if reward < self.min_reward {
+			return Decision::Defer(format!(
+				"reward {reward} is under the minimum of {}",
+				self.min_reward
+			))
+		}
+		Decision::Prove
+	}
+
+	pub fn decide(&self, reward: Balance, stats: &ExecutionStats) -> Decision {
+		if let Some(max_cycles) = self.max_cycles {
+			if stats.padded_cycles > max_cycles {
+				return Decision::Skip(format!(
+					"{} padded cycles is over the maximum of {max_cycles}",
+					stats.padded_cycles
+				))
+			}
+		}
+		if let Decision::Defer(reason) = self.decide_reward(reward) {
+			return Decision::Defer(reason)
+		}
+		let cost = self.cost(stats.padded_cycles);
+		if reward < cost {
+			return Decision::Defer(format!(
+				"reward {reward} does not cover the cost {cost} of {} padded cycles",
+				stats.padded_cycles
+			))
+		}
+		Decision::Prove
+	}
+}
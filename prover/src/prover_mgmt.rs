@@ -0,0 +1,168 @@
+//! Static bindings to the calls, storage and events of the `ProverMgmt` pallet used by the prover.
+//!
+//! The rest of the runtime is reached through the `substrate_node` module generated from
+//! `metadata.scale`. The pallet's items are mirrored here instead, so that the prover builds
+//! against the pallet's current interface whether or not the metadata has been regenerated since
+//! it last changed. Addresses and payloads are not validated against the node's metadata: a
+//! mismatch surfaces as a decoding error, or as a rejected transaction.
+
+use codec::{Decode, Encode};
+use subxt::{
+	ext::sp_core::crypto::AccountId32,
+	metadata::DecodeStaticType,
+	storage::address::{StaticStorageAddress, StorageHasher, StorageMapKey, Yes},
+	tx::StaticTxPayload,
+};
+
+use crate::{
+	chain::{ImageId, RequestId},
+	policy::Balance,
+};
+
+type BlockNumber = u32;
+
+const PALLET: &str = "ProverMgmt";
+
+/// Exclusive right of a prover to fulfill a request for a limited time
+#[derive(Clone, Debug, Decode)]
+pub struct Claim {
+	pub prover: AccountId32,
+	pub expires_at: BlockNumber,
+	pub collateral: Balance,
+}
+
+/// An open request for a proof, as stored in `ProofRequests`
+#[derive(Clone, Debug, Decode)]
+pub struct ProofRequest {
+	pub image_id: ImageId,
+	pub requester: AccountId32,
+	pub reward: Balance,
+	pub args: Vec<Vec<u32>>,
+	pub input_commitment: [u8; 32],
+	pub deadline: BlockNumber,
+	pub claim: Option<Claim>,
+}
+
+/// The lowest bid revealed on an auction so far
+#[derive(Clone, Debug, Decode)]
+pub struct RevealedBid {
+	pub prover: AccountId32,
+	pub price: Balance,
+	pub deposit: Balance,
+}
+
+/// The sealed-bid auction of a request, as stored in `Auctions` until it closes
+#[derive(Clone, Debug, Decode)]
+pub struct Auction {
+	pub bidding_end: BlockNumber,
+	pub reveal_end: BlockNumber,
+	pub bids: u32,
+	pub lowest_bid: Option<RevealedBid>,
+}
+
+#[derive(Clone, Debug, Decode)]
+pub struct ProofRequested {
+	pub request_id: RequestId,
+	pub image_id: ImageId,
+	pub args: Vec<Vec<u32>>,
+}
+
+impl subxt::events::StaticEvent for ProofRequested {
+	const PALLET: &'static str = PALLET;
+	const EVENT: &'static str = "ProofRequested";
+}
+
+#[derive(Clone, Debug, Decode)]
+pub struct ClaimLapsed {
+	pub request: RequestId,
+	pub prover: AccountId32,
+	pub slashed: Balance,
+	pub stake_slashed: Balance,
+}
+
+impl subxt::events::StaticEvent for ClaimLapsed {
+	const PALLET: &'static str = PALLET;
+	const EVENT: &'static str = "ClaimLapsed";
+}
+
+#[derive(Clone, Debug, Decode)]
+pub struct AuctionClosed {
+	pub request: RequestId,
+	pub winner: Option<AccountId32>,
+	pub reward: Balance,
+}
+
+impl subxt::events::StaticEvent for AuctionClosed {
+	const PALLET: &'static str = PALLET;
+	const EVENT: &'static str = "AuctionClosed";
+}
+
+#[derive(Encode)]
+pub struct StoreAndVerifyProof {
+	pub image_id: ImageId,
+	pub request_id: Option<RequestId>,
+	pub receipt_data: Vec<(Vec<u32>, u32)>,
+	pub journal: Vec<u8>,
+}
+
+pub fn store_and_verify_proof(
+	image_id: ImageId,
+	request_id: Option<RequestId>,
+	receipt_data: Vec<(Vec<u32>, u32)>,
+	journal: Vec<u8>,
+) -> StaticTxPayload<StoreAndVerifyProof> {
+	StaticTxPayload::new(
+		PALLET,
+		"store_and_verify_proof",
+		StoreAndVerifyProof { image_id, request_id, receipt_data, journal },
+		[0; 32],
+	)
+	.unvalidated()
+}
+
+#[derive(Encode)]
+pub struct ClaimRequest {
+	pub request_id: RequestId,
+}
+
+pub fn claim_request(request_id: RequestId) -> StaticTxPayload<ClaimRequest> {
+	StaticTxPayload::new(PALLET, "claim_request", ClaimRequest { request_id }, [0; 32])
+		.unvalidated()
+}
+
+/// The bincode-serialized ELF of an uploaded program
+pub fn programs(
+	image_id: ImageId,
+) -> StaticStorageAddress<DecodeStaticType<Vec<u8>>, Yes, (), Yes> {
+	StaticStorageAddress::new(
+		PALLET,
+		"Programs",
+		vec![StorageMapKey::new(&image_id, StorageHasher::Blake2_128Concat)],
+		[0; 32],
+	)
+	.unvalidated()
+}
+
+pub fn proof_requests(
+	request_id: RequestId,
+) -> StaticStorageAddress<DecodeStaticType<ProofRequest>, Yes, (), Yes> {
+	StaticStorageAddress::new(
+		PALLET,
+		"ProofRequests",
+		vec![StorageMapKey::new(&request_id, StorageHasher::Twox64Concat)],
+		[0; 32],
+	)
+	.unvalidated()
+}
+
+pub fn auctions(
+	request_id: RequestId,
+) -> StaticStorageAddress<DecodeStaticType<Auction>, Yes, (), Yes> {
+	StaticStorageAddress::new(
+		PALLET,
+		"Auctions",
+		vec![StorageMapKey::new(&request_id, StorageHasher::Twox64Concat)],
+		[0; 32],
+	)
+	.unvalidated()
+}
@@ -0,0 +1,106 @@
+//! Execution and proving of programs.
+
+use std::fmt;
+
+use risc0_zkvm::{
+	sha::Digest, Executor, ExecutorEnv, MemoryImage, Program, Session, SessionReceipt,
+};
+use risc0_zkvm_platform::{memory::MEM_SIZE, PAGE_SIZE};
+
+use crate::chain::ImageId;
+
+// Compute the image id of the program which was given as serialized bytes, like the chain does
+pub fn compute_image_id(onchain_program: &[u8]) -> Option<ImageId> {
+	let elf: Vec<u8> = bincode::deserialize(onchain_program).ok()?;
+	let program = Program::load_elf(&elf, MEM_SIZE as u32).ok()?;
+	let image = MemoryImage::new(&program, PAGE_SIZE as u32);
+	image.compute_id().as_words().try_into().ok()
+}
+
+// How the executor reports an execution which went over its session limit
+const SESSION_LIMIT_EXCEEDED: &str = "Session limit exceeded";
+
+/// Reasons for an execution not to be recorded
+#[derive(Debug)]
+pub enum ExecutionError {
+	/// The program is not a bincode-serialized RISC Zero ELF
+	InvalidProgram(String),
+	/// The execution was stopped after taking more than the given number of cycles
+	CycleLimit(u64),
+	/// The program failed while executing
+	Failed(String),
+}
+
+impl fmt::Display for ExecutionError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ExecutionError::InvalidProgram(e) => write!(f, "invalid program: {e}"),
+			ExecutionError::CycleLimit(limit) =>
+				write!(f, "execution took more than the maximum of {limit} cycles"),
+			ExecutionError::Failed(e) => write!(f, "execution failed: {e}"),
+		}
+	}
+}
+
+impl std::error::Error for ExecutionError {}
+
+// Run the program which was given as serialized bytes, recording its execution. The execution is
+// stopped once it takes more than `max_cycles`, if given.
+pub fn execute_program(
+	onchain_program: Vec<u8>,
+	args: Vec<Vec<u32>>,
+	max_cycles: Option<u64>,
+) -> Result<Session, ExecutionError> {
+	let mut envbuilder = ExecutorEnv::builder();
+	args.iter().for_each(|a| {
+		envbuilder.add_input(a);
+	});
+	if let Some(max_cycles) = max_cycles {
+		envbuilder.session_limit(usize::try_from(max_cycles).unwrap_or(usize::MAX));
+	}
+
+	let env = envbuilder.build();
+
+	let elf: Vec<u8> = bincode::deserialize(&onchain_program)
+		.map_err(|e| ExecutionError::InvalidProgram(e.to_string()))?;
+	let mut executor =
+		Executor::from_elf(env, &elf).map_err(|e| ExecutionError::InvalidProgram(e.to_string()))?;
+
+	println!("Starting session");
+	executor.run().map_err(|e| match max_cycles {
+		Some(limit) if e.to_string().contains(SESSION_LIMIT_EXCEEDED) =>
+			ExecutionError::CycleLimit(limit),
+		_ => ExecutionError::Failed(e.to_string()),
+	})
+}
+
+// Prove a recorded execution
+pub fn prove_session(session: Session) -> Result<SessionReceipt, String> {
+	println!("Now proving execution");
+	let receipt = session.prove().map_err(|e| format!("proving failed: {e}"))?;
+	println!("Done proving");
+	Ok(receipt)
+}
+
+// Size of a recorded execution, which drives the cost of proving it
+pub struct ExecutionStats {
+	// Cycles the program ran for
+	pub cycles: u64,
+	// Cycles of all segments, padded to their power of two as they are proven. This is more than
+	// the cycles the program ran for, and is what proving costs.
+	pub padded_cycles: u64,
+	pub segments: usize,
+}
+
+pub fn execution_stats(session: &Session) -> ExecutionStats {
+	ExecutionStats {
+		cycles: session.segments.iter().map(|segment| segment.insn_cycles as u64).sum(),
+		padded_cycles: session.segments.iter().map(|segment| 1u64 << segment.po2).sum(),
+		segments: session.segments.len(),
+	}
+}
+
+// Verify a receipt like the chain would, before paying to submit it
+pub fn verify_receipt(session_receipt: &SessionReceipt, image_id: ImageId) -> Result<(), String> {
+	session_receipt.verify(Digest::from(image_id)).map_err(|e| format!("{:?}", e))
+}
@@ -0,0 +1,205 @@
+//! Receipt files, so that proofs can be proven and submitted on different hosts.
+//!
+//! A receipt file starts with `MAGIC` and the little-endian version of its format, followed by
+//! the bincode encoded `ReceiptFile`, and ends with the SHA-256 checksum of everything before it.
+
+use std::{fs, path::Path};
+
+use risc0_zkvm::{SegmentReceipt, SessionReceipt};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+	chain::{ImageId, RequestId},
+	jobs::Error,
+};
+
+/// Start of every receipt file
+pub const MAGIC: [u8; 8] = *b"TOEPRCPT";
+
+/// Version of the receipt file format written by this prover
+pub const VERSION: u16 = 1;
+
+/// Version of risc0 receipts are proven with. It must match the version the chain verifies them
+/// with, and the version of `risc0-zkvm` in `Cargo.toml`.
+pub const RISC0_VERSION: &str = "0.14.0";
+
+const HEADER_LEN: usize = MAGIC.len() + 2;
+const CHECKSUM_LEN: usize = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReceiptFile {
+	pub image_id: ImageId,
+	pub request_id: RequestId,
+	pub risc0_version: String,
+	// Seal and index of every segment receipt
+	pub segments: Vec<(Vec<u32>, u32)>,
+	pub journal: Vec<u8>,
+}
+
+impl ReceiptFile {
+	pub fn new(image_id: ImageId, request_id: RequestId, session_receipt: &SessionReceipt) -> Self {
+		ReceiptFile {
+			image_id,
+			request_id,
+			risc0_version: RISC0_VERSION.into(),
+			segments: session_receipt
+				.segments
+				.iter()
+				.map(|SegmentReceipt { seal, index }| (seal.clone(), *index))
+				.collect(),
+			journal: session_receipt.journal.clone(),
+		}
+	}
+
+	pub fn session_receipt(&self) -> SessionReceipt {
+		SessionReceipt {
+			segments: self
+				.segments
+				.iter()
+				.map(|(seal, index)| SegmentReceipt { seal: seal.clone(), index: *index })
+				.collect(),
+			journal: self.journal.clone(),
+		}
+	}
+
+	pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+		let mut bytes = MAGIC.to_vec();
+		bytes.extend(VERSION.to_le_bytes());
+		bytes.extend(bincode::serialize(self)?);
+		let checksum = Sha256::digest(&bytes);
+		bytes.extend(checksum);
+		Ok(bytes)
+	}
+
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+		if bytes.len() < HEADER_LEN + CHECKSUM_LEN || bytes[..MAGIC.len()] != MAGIC {
+			return Err("Not a receipt file".into())
+		}
+		let (contents, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+		if Sha256::digest(contents).as_slice() != checksum {
+			return Err("Receipt file is corrupted: checksum mismatch".into())
+		}
+
+		let version = u16::from_le_bytes([contents[MAGIC.len()], contents[MAGIC.len() + 1]]);
+		if version != VERSION {
+			return Err(format!(
+				"Receipt file has format version {version}, but only version {VERSION} is supported"
+			)
+			.into())
+		}
+
+		let receipt_file: ReceiptFile = bincode::deserialize(&contents[HEADER_LEN..])?;
+		if receipt_file.risc0_version != RISC0_VERSION {
+			return Err(format!(
+				"Receipt was proven with risc0 {}, but this prover uses risc0 {RISC0_VERSION}",
+				receipt_file.risc0_version
+			)
+			.into())
+		}
+		Ok(receipt_file)
+	}
+
+	pub fn read(path: impl AsRef<Path>) -> Result<Self, Error> {
+		Self::from_bytes(&fs::read(path)?)
+	}
+
+	pub fn write(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+		let path = path.as_ref();
+		// Write to a temporary file first, so that a crash never leaves a truncated receipt
+		let tmp = path.with_extension("tmp");
+		fs::write(&tmp, self.to_bytes()?)?;
+		fs::rename(&tmp, path)?;
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn receipt_file() -> ReceiptFile {
+		ReceiptFile {
+			image_id: [1, 2, 3, 4, 5, 6, 7, 8],
+			request_id: 42,
+			risc0_version: RISC0_VERSION.into(),
+			segments: vec![(vec![9, 10, 11], 0), (vec![12, 13], 1)],
+			journal: vec![14, 15, 16],
+		}
+	}
+
+	// Replace the checksum of a file whose contents were changed
+	fn rechecksum(mut bytes: Vec<u8>) -> Vec<u8> {
+		bytes.truncate(bytes.len() - CHECKSUM_LEN);
+		let checksum = Sha256::digest(&bytes);
+		bytes.extend(checksum);
+		bytes
+	}
+
+	fn error(bytes: &[u8]) -> String {
+		ReceiptFile::from_bytes(bytes).unwrap_err().to_string()
+	}
+
+	#[test]
+	fn round_trips() {
+		let bytes = receipt_file().to_bytes().unwrap();
+		assert_eq!(bytes[..MAGIC.len()], MAGIC);
+		assert_eq!(ReceiptFile::from_bytes(&bytes).unwrap(), receipt_file());
+	}
+
+	#[test]
+	fn round_trips_through_file() {
+		let path = std::env::temp_dir().join(format!("receipt-{}.bin", std::process::id()));
+		receipt_file().write(&path).unwrap();
+		let read = ReceiptFile::read(&path);
+		fs::remove_file(&path).unwrap();
+		assert_eq!(read.unwrap(), receipt_file());
+	}
+
+	#[test]
+	fn round_trips_session_receipt() {
+		let session_receipt = receipt_file().session_receipt();
+		let image_id = receipt_file().image_id;
+		assert_eq!(ReceiptFile::new(image_id, 42, &session_receipt), receipt_file());
+	}
+
+	#[test]
+	fn rejects_bad_magic() {
+		let mut bytes = receipt_file().to_bytes().unwrap();
+		bytes[0] ^= 1;
+		assert_eq!(error(&rechecksum(bytes)), "Not a receipt file");
+		assert_eq!(error(&MAGIC), "Not a receipt file");
+	}
+
+	#[test]
+	fn rejects_bad_checksum() {
+		let mut bytes = receipt_file().to_bytes().unwrap();
+		let last = bytes.len() - CHECKSUM_LEN - 1;
+		bytes[last] ^= 1;
+		assert_eq!(error(&bytes), "Receipt file is corrupted: checksum mismatch");
+	}
+
+	#[test]
+	fn rejects_other_version() {
+		let mut bytes = receipt_file().to_bytes().unwrap();
+		bytes[MAGIC.len()..HEADER_LEN].copy_from_slice(&(VERSION + 1).to_le_bytes());
+		assert_eq!(
+			error(&rechecksum(bytes)),
+			format!(
+				"Receipt file has format version {}, but only version {VERSION} is supported",
+				VERSION + 1
+			)
+		);
+	}
+
+	#[test]
+	fn rejects_other_risc0_version() {
+		let receipt_file = ReceiptFile { risc0_version: "0.13.0".into(), ..receipt_file() };
+		assert_eq!(
+			error(&receipt_file.to_bytes().unwrap()),
+			format!(
+				"Receipt was proven with risc0 0.13.0, but this prover uses risc0 {RISC0_VERSION}"
+			)
+		);
+	}
+}